@@ -1,7 +1,12 @@
 extern crate core;
 extern crate oasis_game_contract;
+extern crate serde_json;
 
 use core::Game;
+use core::config::{self, TableConfig};
+use core::entropy::{self, TableEntropy};
+use core::history::HandHistory;
+use core::legal;
 use oasis_game_contract::gameserver::*;
 
 use owasm_std::logger::debug;
@@ -17,26 +22,167 @@ impl ServerFactory {
     }
 }
 
+// Table configs and hand histories are stored as a length slot followed by 32 byte chunks of JSON.
+// The name fills the first 8 bytes of the key, so a longer one would run into the game id.
+fn storage_key(name: &[u8], game_id: u64, index: u64, slot: u64) -> owasm_std::types::H256 {
+    assert!(name.len() <= 8, "Storage names are at most 8 bytes");
+    let mut key = [0u8; 32];
+    key[..name.len()].copy_from_slice(name);
+    key[8..16].copy_from_slice(&game_id.to_le_bytes());
+    key[16..24].copy_from_slice(&slot.to_le_bytes());
+    key[24..32].copy_from_slice(&index.to_le_bytes());
+    owasm_std::types::H256::from(key)
+}
+
+fn store_bytes(name: &[u8], game_id: u64, index: u64, bytes: &[u8]) {
+    let mut length = [0u8; 32];
+    length[..8].copy_from_slice(&(bytes.len() as u64).to_le_bytes());
+    owasm_ethereum::write(&storage_key(name, game_id, index, 0), &length);
+
+    for (i, chunk) in bytes.chunks(32).enumerate() {
+        let mut slot = [0u8; 32];
+        slot[..chunk.len()].copy_from_slice(chunk);
+        owasm_ethereum::write(&storage_key(name, game_id, index, i as u64 + 1), &slot);
+    }
+}
+
+fn load_bytes(name: &[u8], game_id: u64, index: u64) -> Vec<u8> {
+    let mut length = [0u8; 8];
+    length.copy_from_slice(&owasm_ethereum::read(&storage_key(name, game_id, index, 0))[..8]);
+    let length = u64::from_le_bytes(length) as usize;
+
+    let mut bytes = Vec::with_capacity(length);
+    let mut slot = 1;
+    while bytes.len() < length {
+        let chunk = owasm_ethereum::read(&storage_key(name, game_id, index, slot));
+        let remaining = (length - bytes.len()).min(32);
+        bytes.extend_from_slice(&chunk[..remaining]);
+        slot += 1;
+    }
+    bytes
+}
+
+fn store_config(game_id: u64, config: &TableConfig) {
+    store_bytes(b"config", game_id, 0, &config.to_bytes());
+}
+
+fn load_config(game_id: u64) -> Vec<u8> {
+    load_bytes(b"config", game_id, 0)
+}
+
+// The entropy every player sent with ready(). Storage is confidential, so no player can read the others'
+fn load_entropy(game_id: u64) -> TableEntropy {
+    TableEntropy::from_bytes(game_id, &load_bytes(b"entropy", game_id, 0)).expect("Could not read table entropy")
+}
+
+// Archive the hand that just finished, if it is not archived yet, and return it. Histories only hold public cards.
+fn archive_hand(server: &mut GameServer, game_id: u64, player_id: u64) -> Option<HandHistory> {
+    let state = match server.get_state(game_id, player_id) {
+        Ok(state) => state,
+        Err(_) => return None
+    };
+    let hand = serde_json::from_slice::<serde_json::Value>(&state).ok()
+        .and_then(|value| legal::state_from_value(&value))
+        .and_then(|state| state.last_hand);
+
+    if let Some(hand) = hand {
+        let index = hand.hand_number as u64;
+        if load_bytes(b"history", game_id, index).is_empty() {
+            let bytes = serde_json::to_vec(&hand).expect("Could not write hand history");
+            store_bytes(b"history", game_id, index, &bytes);
+            return Some(hand);
+        }
+    }
+    None
+}
+
+// Store the commitment to the deck of the hand being played, returns it the first time it is seen
+fn commit_hand(server: &mut GameServer, game_id: u64, player_id: u64) -> Option<(u32, String)> {
+    let state = server.get_state(game_id, player_id).ok()?;
+    let state = serde_json::from_slice::<serde_json::Value>(&state).ok()
+        .and_then(|value| legal::state_from_value(&value))?;
+    let commitment = state.deck_commitment?;
+
+    let index = state.hand_number as u64;
+    if !load_bytes(b"commit", game_id, index).is_empty() {
+        return None;
+    }
+    store_bytes(b"commit", game_id, index, commitment.as_bytes());
+    Some((state.hand_number, commitment))
+}
+
+// Validate a move against the player's view of the game, returns the reason it was rejected
+fn check_move(server: &mut GameServer, game_id: u64, player_id: u64, game_move: &[u8]) -> Result<(), String> {
+    let state = server.get_state(game_id, player_id).map_err(|err| err.to_string())?;
+    let state: serde_json::Value = serde_json::from_slice(&state).map_err(|err| err.to_string())?;
+    let state = legal::state_from_value(&state).ok_or(String::from("Not a poker state"))?;
+
+    let game_move: serde_json::Value = serde_json::from_slice(game_move).map_err(|err| err.to_string())?;
+    let args = game_move.get("args").cloned().unwrap_or(serde_json::Value::Null);
+
+    legal::check_move(&state, player_id as u16, &args).map(|_| ()).map_err(|err| err.to_string())
+}
+
 #[owasm_abi_derive::contract]
 trait GameServerContract {
-    fn create(&mut self, _tokens: Vec<u8>) {
+    fn create(&mut self, _tokens: Vec<u8>, _config: Vec<u8>) {
+        let table_config = TableConfig::from_bytes(&_config).expect("Invalid table config");
+
+        // Every seat gets exactly one player token
+        let players: Vec<serde_json::Value> = serde_json::from_slice(&_tokens).expect("Invalid player tokens");
+        assert!(players.len() == table_config.num_seats as usize,
+            "Got {} player tokens for {} seats", players.len(), table_config.num_seats);
+        config::set_table_config(table_config.clone()).expect("Could not set table config");
+
         let mut server = ServerFactory::create();
         let id = server.create(_tokens.clone());
+        store_config(id, &table_config);
         self.NewGame(id, _tokens);
     }
 
     fn ready(&mut self, _game_id: u64, token: Vec<u8>, _entropy: Vec<u8>) -> u64 {
+        let mut table_entropy = load_entropy(_game_id);
+        table_entropy.add(_entropy.clone());
+        store_bytes(b"entropy", _game_id, 0, &table_entropy.to_bytes());
+
+        // The game is set up here and not in create, so it needs the table config again.
+        // It keeps the entropy in its state, and seeds every hand from it.
+        let table_config = TableConfig::from_bytes(&load_config(_game_id)).expect("Invalid table config");
+        config::set_table_config(table_config).expect("Could not set table config");
+        entropy::set_table_entropy(Some(table_entropy));
+
         let mut server = ServerFactory::create();
         let (player_id, started) = server.ready(_game_id, token, &mut _entropy.clone()).expect("Could not set ready status");
         if started {
             self.GameStarted(_game_id);
+            if let Some((hand_number, commitment)) = commit_hand(&mut server, _game_id, player_id as u64) {
+                self.HandCommitted(_game_id, hand_number as u64, commitment.into_bytes());
+            }
         }
         player_id as u64
     }
 
     fn sendAction(&mut self, _game_id: u64,  _player_id: u64, _game_move: Vec<u8>) {
         let mut server = ServerFactory::create();
+
+        // Tell the caller why a move is invalid instead of dropping it silently
+        if let Err(reason) = check_move(&mut server, _game_id, _player_id, &_game_move) {
+            debug(&format!("Rejected move from player {}: {}", _player_id, reason));
+            self.MoveRejected(_game_id, _player_id, reason.into_bytes());
+            return;
+        }
+
         server.handle_action(_game_id, _player_id, _game_move);
+
+        // Reveal the seed of the hand that just ended, then commit to the next deck
+        if let Some(hand) = archive_hand(&mut server, _game_id, _player_id) {
+            if let Some(seed) = hand.seed {
+                self.HandRevealed(_game_id, hand.hand_number as u64, seed.into_bytes());
+            }
+        }
+        if let Some((hand_number, commitment)) = commit_hand(&mut server, _game_id, _player_id) {
+            self.HandCommitted(_game_id, hand_number as u64, commitment.into_bytes());
+        }
         // Web3 still can't properly handle empty events.
         self.GameEvent(_game_id, _game_id);
     }
@@ -60,6 +206,32 @@ trait GameServerContract {
         server.get_moves(_game_id).expect("Could not get moves")
     }
 
+    #[constant]
+    fn getConfig(&mut self, _game_id: u64) -> Vec<u8> {
+        load_config(_game_id)
+    }
+
+    // A finished hand as a JSON HandHistory, empty if that hand was not played yet
+    #[constant]
+    fn getHandHistory(&mut self, _game_id: u64, _hand_number: u64) -> Vec<u8> {
+        load_bytes(b"history", _game_id, _hand_number)
+    }
+
+    // The commitment to the deck of a hand as hex, empty if that hand was not dealt yet
+    #[constant]
+    fn getCommitment(&mut self, _game_id: u64, _hand_number: u64) -> Vec<u8> {
+        load_bytes(b"commit", _game_id, _hand_number)
+    }
+
+    #[constant]
+    fn getLegalActions(&mut self, _game_id: u64, _player_id: u64) -> Vec<u8> {
+        let mut server = ServerFactory::create();
+        let state = server.get_state(_game_id, _player_id).expect("Could not get state");
+        let value: serde_json::Value = serde_json::from_slice(&state).expect("Could not read state");
+        let state = legal::state_from_value(&value).expect("Not a poker state");
+        serde_json::to_vec(&legal::legal_actions(&state, _player_id as u16)).expect("Could not write legal actions")
+    }
+
     #[event]
     fn GameEvent(&mut self, indexed_id: u64, _id: u64);
     #[event]
@@ -68,4 +240,10 @@ trait GameServerContract {
     fn NewGame(&mut self, _id: u64, _players: Vec<u8>);
     #[event]
     fn GameStarted(&mut self, indexed_id: u64);
+    #[event]
+    fn MoveRejected(&mut self, indexed_id: u64, _player_id: u64, _reason: Vec<u8>);
+    #[event]
+    fn HandCommitted(&mut self, indexed_id: u64, _hand_number: u64, _commitment: Vec<u8>);
+    #[event]
+    fn HandRevealed(&mut self, indexed_id: u64, _hand_number: u64, _seed: Vec<u8>);
 }
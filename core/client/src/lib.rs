@@ -1,5 +1,6 @@
 extern crate console_error_panic_hook;
 extern crate wasm_bindgen;
+extern crate serde_json;
 
 extern crate oasis_game_core;
 extern crate oasis_game_client_proxy;
@@ -11,15 +12,63 @@ use wasm_bindgen::prelude::*;
 use oasis_game_client_proxy::{create_proxy, Proxy};
 use oasis_game_core::StoreFactory;
 use core::Game;
+use core::legal;
+use core::scenario::{self, Scenario};
+use serde_json::Value;
 
+/// Start a game, from a new table or from a position given as a JSON Scenario.
 #[wasm_bindgen]
-pub fn create (player_id: u16, players: Vec<u16>, multiplayer: bool, server: bool, seed: u32) -> Proxy {
+pub fn create (player_id: u16, players: Vec<u16>, multiplayer: bool, server: bool, seed: u32, scenario: Option<String>) -> Proxy {
     panic::set_hook(Box::new(console_error_panic_hook::hook));
+    let scenario = scenario.map(|json| serde_json::from_str::<Scenario>(&json).expect("Could not read scenario"));
+    scenario::set_scenario(scenario).expect("Invalid scenario");
     let game = Game {};
     let store = game.create(player_id, players, multiplayer, server, true, Some(seed as u128));
     create_proxy(store)
 }
 
+/// Legal actions for a player, given the game state the client holds.
+#[wasm_bindgen]
+pub fn legal_actions (state: &JsValue, player_id: u16) -> JsValue {
+    let value: Value = state.into_serde().expect("Could not read state");
+    let state = legal::state_from_value(&value).expect("Not a poker state");
+    JsValue::from_serde(&legal::legal_actions(&state, player_id)).expect("Could not write legal actions")
+}
+
+/// The last finished hand in the PokerStars text format, empty before the first hand ends.
+#[wasm_bindgen]
+pub fn hand_history_text (state: &JsValue, table_name: &str, timestamp: &str) -> String {
+    let value: Value = state.into_serde().expect("Could not read state");
+    let state = legal::state_from_value(&value).expect("Not a poker state");
+    match state.last_hand {
+        Some(hand) => hand.to_pokerstars(table_name, timestamp),
+        None => String::new()
+    }
+}
+
+/// The last finished hand as Open Hand History JSON, empty before the first hand ends.
+#[wasm_bindgen]
+pub fn hand_history_ohh (state: &JsValue, table_name: &str, start_date_utc: &str) -> String {
+    let value: Value = state.into_serde().expect("Could not read state");
+    let state = legal::state_from_value(&value).expect("Not a poker state");
+    match state.last_hand {
+        Some(hand) => serde_json::to_string(&hand.to_ohh(table_name, start_date_utc)).expect("Could not write hand history"),
+        None => String::new()
+    }
+}
+
+/// Check the last finished hand was dealt from the deck committed to when it started.
+/// Empty when the deal checks out, otherwise the reason it does not.
+#[wasm_bindgen]
+pub fn verify_last_hand (state: &JsValue) -> String {
+    let value: Value = state.into_serde().expect("Could not read state");
+    let state = legal::state_from_value(&value).expect("Not a poker state");
+    match state.last_hand {
+        Some(hand) => hand.verify_deal().err().map(|err| err.to_string()).unwrap_or_default(),
+        None => String::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
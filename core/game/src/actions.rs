@@ -0,0 +1,276 @@
+/**
+ * Module that defines the move protocol sent to poker_move
+ *
+ * A move is a versioned envelope around one action. It can be sent in two encodings,
+ * as the first element of the move arguments:
+ *
+ * JSON, as an object:
+ *   {"version": 1, "action": {"type": "Fold"}}
+ *   {"version": 1, "action": {"type": "Raise", "to": 12}}
+ *   {"version": 1, "action": {"type": "BetPreset", "preset": "HalfPot"}}
+ *
+ * Compact binary, as a hex string such as "0x01040c000000":
+ *   byte 0     protocol version
+ *   byte 1     opcode, see `PokerAction::opcode`
+ *   bytes 2..  payload: a little-endian amount for Bet and Raise, 4 bytes when it
+ *              fits in a u32 and 8 bytes otherwise,
+ *              one byte for BetPreset (0 Min, 1 HalfPot, 2 Pot), empty otherwise
+ *
+ * The original integer codes ([0] fold, [1] check or call, [2] bet or raise,
+ * [3] all in, [4] straddle) are still accepted. [99] confirmed the end of a hand, the next
+ * hand is now dealt without waiting for anyone, so it is rejected like any unknown code.
+ *
+ * Show and Muck are only played at the showdown.
+ */
+
+use std::error::Error;
+use serde_json;
+use serde_json::Value;
+use chips::Chips;
+use Errors;
+
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// Bet sizes resolved by the engine against the current pot.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Preset {
+    Min,
+    HalfPot,
+    Pot
+}
+
+/// A single poker action. Amounts are the total the player will have
+/// on the table this betting round, not the amount added.
+/// Check is only legal with nothing to call, a call with nothing to call is a check.
+/// Bet and Raise are both accepted whether or not there is a bet to raise.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type")]
+pub enum PokerAction {
+    Fold,
+    Check,
+    Call,
+    Bet { to: Chips },
+    Raise { to: Chips },
+    BetPreset { preset: Preset },
+    AllIn,
+    Straddle,
+    Muck,
+    Show
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PokerMove {
+    pub version: u8,
+    pub action: PokerAction
+}
+
+impl PokerMove {
+
+    pub fn new(action: PokerAction) -> PokerMove {
+        PokerMove {
+            version: PROTOCOL_VERSION,
+            action: action
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).expect("Could not serialize move")
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+
+        let mut bytes = vec![self.version, self.action.opcode()];
+
+        match self.action {
+            PokerAction::Bet { to } | PokerAction::Raise { to } => {
+                if to.0 <= u32::max_value() as u64 {
+                    bytes.extend_from_slice(&(to.0 as u32).to_le_bytes());
+                } else {
+                    bytes.extend_from_slice(&to.0.to_le_bytes());
+                }
+            },
+            PokerAction::BetPreset { preset } => {
+                bytes.push(match preset {
+                    Preset::Min => 0,
+                    Preset::HalfPot => 1,
+                    Preset::Pot => 2
+                });
+            },
+            _ => ()
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<PokerMove, Box<Error>> {
+
+        if bytes.len() < 2 {
+            return Err(Box::new(Errors::InvalidMove));
+        }
+        check_version(bytes[0])?;
+
+        let payload = &bytes[2..];
+        let action = match (bytes[1], payload.len()) {
+            (0, 0) => PokerAction::Fold,
+            (1, 0) => PokerAction::Check,
+            (2, 0) => PokerAction::Call,
+            (3, 4) | (3, 8) => PokerAction::Bet { to: read_amount(payload) },
+            (4, 4) | (4, 8) => PokerAction::Raise { to: read_amount(payload) },
+            (5, 1) => PokerAction::BetPreset {
+                preset: match payload[0] {
+                    0 => Preset::Min,
+                    1 => Preset::HalfPot,
+                    2 => Preset::Pot,
+                    _ => return Err(Box::new(Errors::InvalidMove))
+                }
+            },
+            (6, 0) => PokerAction::AllIn,
+            (7, 0) => PokerAction::Straddle,
+            (8, 0) => PokerAction::Muck,
+            (10, 0) => PokerAction::Show,
+            _ => return Err(Box::new(Errors::InvalidMove))
+        };
+
+        Ok(PokerMove::new(action))
+    }
+}
+
+impl PokerAction {
+
+    pub fn opcode(&self) -> u8 {
+        match *self {
+            PokerAction::Fold => 0,
+            PokerAction::Check => 1,
+            PokerAction::Call => 2,
+            PokerAction::Bet { .. } => 3,
+            PokerAction::Raise { .. } => 4,
+            PokerAction::BetPreset { .. } => 5,
+            PokerAction::AllIn => 6,
+            PokerAction::Straddle => 7,
+            PokerAction::Muck => 8,
+            // 9 confirmed the end of a hand
+            PokerAction::Show => 10
+        }
+    }
+}
+
+fn check_version(version: u8) -> Result<(), Box<Error>> {
+    if version != PROTOCOL_VERSION {
+        return Err(Box::new(Errors::UnsupportedVersion(version)));
+    }
+    Ok(())
+}
+
+// A little-endian amount of 4 or 8 bytes
+fn read_amount(bytes: &[u8]) -> Chips {
+    let mut amount = [0u8; 8];
+    amount[..bytes.len()].copy_from_slice(bytes);
+    Chips(u64::from_le_bytes(amount))
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.trim_start_matches("0x");
+    if !hex.is_ascii() || hex.len() % 2 != 0 {
+        return None;
+    }
+
+    (0..hex.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+// The original integer codes
+fn from_legacy(code: u64, size: Option<&Value>) -> Result<PokerAction, Box<Error>> {
+
+    let action = match code {
+        0 => PokerAction::Fold,
+        1 => PokerAction::Call,
+        2 => match size {
+            None => PokerAction::BetPreset { preset: Preset::Min },
+            Some(size) => match (size.as_u64(), size.as_str()) {
+                (Some(to), _) => PokerAction::Raise { to: Chips(to) },
+                (_, Some("min")) => PokerAction::BetPreset { preset: Preset::Min },
+                (_, Some("half_pot")) => PokerAction::BetPreset { preset: Preset::HalfPot },
+                (_, Some("pot")) => PokerAction::BetPreset { preset: Preset::Pot },
+                (_, Some("all_in")) => PokerAction::AllIn,
+                _ => return Err(Box::new(Errors::InvalidMove))
+            }
+        },
+        3 => PokerAction::AllIn,
+        4 => PokerAction::Straddle,
+        _ => return Err(Box::new(Errors::InvalidMove))
+    };
+
+    Ok(action)
+}
+
+/**
+ * Decode the arguments of a poker_move in any of the supported encodings.
+ */
+pub fn decode_move(args: &Value) -> Result<PokerAction, Box<Error>> {
+
+    let args = args.as_array().ok_or(Box::new(Errors::InvalidMove))?;
+    let first = args.get(0).ok_or(Box::new(Errors::InvalidMove))?;
+
+    if let Some(code) = first.as_u64() {
+        return from_legacy(code, args.get(1));
+    }
+
+    if let Some(hex) = first.as_str() {
+        let bytes = from_hex(hex).ok_or(Box::new(Errors::InvalidMove))?;
+        return Ok(PokerMove::from_bytes(&bytes)?.action);
+    }
+
+    // Check the version before the action, so newer actions report the right error
+    let version = first.get("version")
+        .and_then(|version| version.as_u64())
+        .ok_or(Box::new(Errors::InvalidMove))?;
+    check_version(version.min(u8::max_value() as u64) as u8)?;
+
+    let poker_move: PokerMove = serde_json::from_value(first.clone())
+        .map_err(|_| Box::new(Errors::InvalidMove))?;
+
+    Ok(poker_move.action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_encoding() {
+        let raise = PokerMove::new(PokerAction::Raise { to: Chips(12) });
+        assert_eq!(raise.to_json(), json!({"version": 1, "action": {"type": "Raise", "to": 12}}));
+        assert_eq!(decode_move(&json!([raise.to_json()])).unwrap(), raise.action);
+    }
+
+    #[test]
+    fn binary_encoding() {
+        let raise = PokerMove::new(PokerAction::Raise { to: Chips(12) });
+        assert_eq!(raise.to_bytes(), vec![1, 4, 12, 0, 0, 0]);
+        assert_eq!(decode_move(&json!(["0x01040c000000"])).unwrap(), raise.action);
+        assert!(decode_move(&json!(["aé1"])).is_err());
+
+        let big_bet = PokerMove::new(PokerAction::Bet { to: Chips(5_000_000_000) });
+        assert_eq!(big_bet.to_bytes().len(), 10);
+        assert_eq!(PokerMove::from_bytes(&big_bet.to_bytes()).unwrap(), big_bet);
+
+        let preset = PokerMove::new(PokerAction::BetPreset { preset: Preset::Pot });
+        assert_eq!(PokerMove::from_bytes(&preset.to_bytes()).unwrap(), preset);
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        assert!(decode_move(&json!([{"version": 2, "action": {"type": "Fold"}}])).is_err());
+        assert!(PokerMove::from_bytes(&[2, 0]).is_err());
+    }
+
+    #[test]
+    fn integer_codes_still_work() {
+        assert_eq!(decode_move(&json!([1])).unwrap(), PokerAction::Call);
+        assert_eq!(decode_move(&json!([2, "pot"])).unwrap(), PokerAction::BetPreset { preset: Preset::Pot });
+        assert!(decode_move(&json!([7])).is_err());
+        assert!(decode_move(&json!([99])).is_err());
+        assert!(PokerMove::from_bytes(&[1, 9]).is_err());
+    }
+}
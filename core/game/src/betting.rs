@@ -0,0 +1,186 @@
+/**
+ * Module that resolves bet and raise sizes
+ */
+
+use chips::Chips;
+
+/// How much a player may bet or raise.
+/// Pot-limit caps a raise at the size of the pot after calling.
+/// Fixed-limit raises by the small bet preflop and on the flop, by the big bet on the turn
+/// and river, and allows at most max_bets bets and raises per street.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum BettingStructure {
+    NoLimit,
+    PotLimit,
+    FixedLimit {
+        small_bet: Chips,
+        big_bet: Chips,
+        max_bets: u8
+    }
+}
+
+/// The amount a player asked to bet or raise to.
+/// Presets are resolved to exact chip amounts against the current pot.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum BetSize {
+    To(Chips),
+    MinRaise,
+    HalfPot,
+    Pot,
+    AllIn
+}
+
+/// What a player is facing when choosing a raise size.
+/// The pot includes every bet already on the table.
+/// Sizes saturate instead of overflowing, moving the chips is checked separately.
+#[derive(Clone, Debug)]
+pub struct RaiseContext {
+    pub stack: Chips,
+    pub on_table: Chips,
+    pub bet_amount: Chips,
+    pub last_raise: Chips,
+    pub pot: Chips,
+    pub bets_this_street: u8
+}
+
+impl RaiseContext {
+
+    pub fn to_call(&self) -> Chips {
+        self.bet_amount.saturating_sub(self.on_table)
+    }
+
+    pub fn all_in_to(&self) -> Chips {
+        self.stack.saturating_add(self.on_table)
+    }
+
+    // A raise must at least match the last full raise, an opening bet the big blind
+    pub fn min_raise_to(&self) -> Chips {
+        self.bet_amount.saturating_add(self.last_raise)
+    }
+
+    // Raising the pot means calling first, then betting the size of the pot after the call
+    pub fn pot_raise_to(&self) -> Chips {
+        self.bet_amount.saturating_add(self.pot).saturating_add(self.to_call())
+    }
+
+    pub fn half_pot_raise_to(&self) -> Chips {
+        let (half_pot, _) = self.pot.saturating_add(self.to_call()).split(2);
+        self.bet_amount.saturating_add(half_pot)
+    }
+}
+
+// The smallest and largest totals a player may raise to, None if they cannot raise.
+// Going all in is always allowed, even if it is smaller than a minimum raise.
+// For fixed-limit, last_raise must be the bet unit of the street.
+pub fn raise_limits(ctx: &RaiseContext, structure: &BettingStructure) -> Option<(Chips, Chips)> {
+
+    let all_in = ctx.all_in_to();
+    if all_in <= ctx.bet_amount {
+        return None;
+    }
+
+    let (min_raise_to, max_raise_to) = match *structure {
+        BettingStructure::NoLimit => (ctx.min_raise_to(), all_in),
+        BettingStructure::PotLimit => (ctx.min_raise_to(), ctx.pot_raise_to()),
+        BettingStructure::FixedLimit { max_bets, .. } => {
+            if ctx.bets_this_street >= max_bets {
+                return None;
+            }
+            (ctx.min_raise_to(), ctx.min_raise_to())
+        }
+    };
+
+    let min_raise_to = min_raise_to.min(all_in);
+    let max_raise_to = max_raise_to.min(all_in).max(min_raise_to);
+
+    Some((min_raise_to, max_raise_to))
+}
+
+// Resolve a size to the total a player will have on the table after raising.
+// Presets are kept within the limits of the betting structure, returns None if the raise is not legal.
+// All in is the whole stack, so it is not legal when the structure caps the raise below it.
+pub fn resolve_raise(size: &BetSize, ctx: &RaiseContext, structure: &BettingStructure) -> Option<Chips> {
+
+    let (min_raise_to, max_raise_to) = raise_limits(ctx, structure)?;
+
+    let raise_to = match *size {
+        BetSize::To(amount) => amount,
+        BetSize::MinRaise => min_raise_to,
+        BetSize::HalfPot => ctx.half_pot_raise_to().max(min_raise_to).min(max_raise_to),
+        BetSize::Pot => ctx.pot_raise_to().max(min_raise_to).min(max_raise_to),
+        BetSize::AllIn => ctx.all_in_to()
+    };
+
+    if raise_to < min_raise_to || raise_to > max_raise_to {
+        return None;
+    }
+
+    Some(raise_to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facing_bet() -> RaiseContext {
+        // Blinds 1/2, one caller, facing a raise to 6 from the big blind
+        RaiseContext {
+            stack: Chips(60),
+            on_table: Chips(2),
+            bet_amount: Chips(6),
+            last_raise: Chips(4),
+            pot: Chips(13),
+            bets_this_street: 2
+        }
+    }
+
+    #[test]
+    fn presets_resolve_to_exact_amounts() {
+        let ctx = facing_bet();
+        assert_eq!(resolve_raise(&BetSize::MinRaise, &ctx, &BettingStructure::NoLimit), Some(Chips(10)));
+        assert_eq!(resolve_raise(&BetSize::HalfPot, &ctx, &BettingStructure::NoLimit), Some(Chips(14)));
+        assert_eq!(resolve_raise(&BetSize::Pot, &ctx, &BettingStructure::NoLimit), Some(Chips(23)));
+        assert_eq!(resolve_raise(&BetSize::AllIn, &ctx, &BettingStructure::NoLimit), Some(Chips(62)));
+    }
+
+    #[test]
+    fn raise_must_match_the_last_raise() {
+        let ctx = facing_bet();
+        assert_eq!(resolve_raise(&BetSize::To(Chips(9)), &ctx, &BettingStructure::NoLimit), None);
+        assert_eq!(resolve_raise(&BetSize::To(Chips(10)), &ctx, &BettingStructure::NoLimit), Some(Chips(10)));
+        assert_eq!(resolve_raise(&BetSize::To(Chips(63)), &ctx, &BettingStructure::NoLimit), None);
+    }
+
+    #[test]
+    fn short_all_in_is_allowed_below_the_minimum() {
+        let mut ctx = facing_bet();
+        ctx.stack = Chips(6);
+        assert_eq!(resolve_raise(&BetSize::To(Chips(8)), &ctx, &BettingStructure::NoLimit), Some(Chips(8)));
+        assert_eq!(resolve_raise(&BetSize::Pot, &ctx, &BettingStructure::NoLimit), Some(Chips(8)));
+    }
+
+    #[test]
+    fn pot_limit_caps_the_raise_at_the_pot() {
+        let ctx = facing_bet();
+        let structure = BettingStructure::PotLimit;
+        assert_eq!(raise_limits(&ctx, &structure), Some((Chips(10), Chips(23))));
+        assert_eq!(resolve_raise(&BetSize::AllIn, &ctx, &structure), None);
+        assert_eq!(resolve_raise(&BetSize::To(Chips(24)), &ctx, &structure), None);
+
+        // A stack the pot covers can still go all in
+        let mut ctx = facing_bet();
+        ctx.stack = Chips(15);
+        assert_eq!(resolve_raise(&BetSize::AllIn, &ctx, &structure), Some(Chips(17)));
+    }
+
+    #[test]
+    fn fixed_limit_raises_by_one_unit_until_capped() {
+        let mut ctx = facing_bet();
+        let structure = BettingStructure::FixedLimit { small_bet: Chips(4), big_bet: Chips(8), max_bets: 3 };
+        assert_eq!(resolve_raise(&BetSize::Pot, &ctx, &structure), Some(Chips(10)));
+        assert_eq!(resolve_raise(&BetSize::To(Chips(14)), &ctx, &structure), None);
+
+        ctx.bets_this_street = 3;
+        assert_eq!(raise_limits(&ctx, &structure), None);
+    }
+}
@@ -0,0 +1,108 @@
+/**
+ * Module that defines the amount type used for stacks, bets and pots
+ */
+
+use std::fmt;
+use std::iter::Sum;
+
+/// An amount of chips, serialized as a plain number.
+/// There is deliberately no + or -, moving chips uses the checked methods and
+/// fails instead of wrapping. Bet sizing may saturate, the move is checked anyway.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Chips(pub u64);
+
+impl Chips {
+
+    pub fn zero() -> Chips {
+        Chips(0)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn checked_add(self, other: Chips) -> Option<Chips> {
+        self.0.checked_add(other.0).map(Chips)
+    }
+
+    pub fn checked_sub(self, other: Chips) -> Option<Chips> {
+        self.0.checked_sub(other.0).map(Chips)
+    }
+
+    pub fn checked_mul(self, times: u64) -> Option<Chips> {
+        self.0.checked_mul(times).map(Chips)
+    }
+
+    pub fn saturating_add(self, other: Chips) -> Chips {
+        Chips(self.0.saturating_add(other.0))
+    }
+
+    pub fn saturating_sub(self, other: Chips) -> Chips {
+        Chips(self.0.saturating_sub(other.0))
+    }
+
+    pub fn saturating_mul(self, times: u64) -> Chips {
+        Chips(self.0.saturating_mul(times))
+    }
+
+    // Splits into equal shares, returns (share, remainder)
+    pub fn split(self, ways: u64) -> (Chips, Chips) {
+        (Chips(self.0 / ways), Chips(self.0 % ways))
+    }
+
+    // Sum of many amounts, None if it does not fit
+    pub fn checked_sum<'a, I: IntoIterator<Item = &'a Chips>>(amounts: I) -> Option<Chips> {
+        amounts.into_iter().fold(Some(Chips::zero()), |total, amount| total?.checked_add(*amount))
+    }
+}
+
+impl From<u64> for Chips {
+    fn from(amount: u64) -> Chips {
+        Chips(amount)
+    }
+}
+
+impl From<u32> for Chips {
+    fn from(amount: u32) -> Chips {
+        Chips(amount as u64)
+    }
+}
+
+impl fmt::Display for Chips {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// Totals used for sizing, saturates like the other sizing math
+impl Sum for Chips {
+    fn sum<I: Iterator<Item = Chips>>(iter: I) -> Chips {
+        iter.fold(Chips::zero(), |total, amount| total.saturating_add(amount))
+    }
+}
+
+impl<'a> Sum<&'a Chips> for Chips {
+    fn sum<I: Iterator<Item = &'a Chips>>(iter: I) -> Chips {
+        iter.cloned().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn arithmetic_is_checked() {
+        assert_eq!(Chips(5).checked_sub(Chips(6)), None);
+        assert_eq!(Chips(u64::max_value()).checked_add(Chips(1)), None);
+        assert_eq!(Chips::checked_sum(&[Chips(u64::max_value()), Chips(1)]), None);
+        assert_eq!(Chips::checked_sum(&[Chips(3), Chips(4)]), Some(Chips(7)));
+    }
+
+    #[test]
+    fn serializes_as_a_number() {
+        assert_eq!(serde_json::to_string(&Chips(5_000_000_000)).unwrap(), "5000000000");
+        assert_eq!(serde_json::from_str::<Chips>("64").unwrap(), Chips(64));
+    }
+}
@@ -0,0 +1,188 @@
+/**
+ * Module that commits to the deck of every hand before it is dealt, and checks the deal
+ * against the commitment once the seed of the hand is revealed
+ */
+
+use std::error::Error;
+use tiny_keccak::Keccak;
+use deck::Deck;
+use events::HandEvent;
+use history::HandHistory;
+use Errors;
+
+// Keeps these hashes apart from any other keccak256 hash of the same bytes
+const COMMITMENT_DOMAIN: &[u8] = b"oasis-poker/deck-commitment/v1";
+
+// Seeds are published as 32 hex digits, most significant first
+pub fn seed_to_hex(seed: u128) -> String {
+    format!("{:032x}", seed)
+}
+
+pub fn seed_from_hex(hex: &str) -> Option<u128> {
+    if hex.len() != 32 {
+        return None;
+    }
+    u128::from_str_radix(hex, 16).ok()
+}
+
+/**
+ * The commitment to a hand, published before any card is dealt:
+ *
+ *   keccak256(domain, seed, suit_1, rank_1, ..., suit_52, rank_52)
+ *
+ * as 64 lowercase hex digits, where the domain is COMMITMENT_DOMAIN, the seed is 16 little
+ * endian bytes and the cards are one byte each, in the order they come off the deck.
+ * The seed keeps the deck order secret until it is revealed, and the deck order makes
+ * the commitment hold whatever deck source the table uses.
+ */
+pub fn commit(seed: u128, deck: &Deck) -> String {
+
+    let mut keccak = Keccak::new_keccak256();
+    keccak.update(COMMITMENT_DOMAIN);
+    keccak.update(&seed.to_le_bytes());
+    for card in deck.dealing_order() {
+        keccak.update(&[card.suit, card.rank]);
+    }
+
+    let mut hash = [0 as u8; 32];
+    keccak.finalize(&mut hash);
+    hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+impl HandHistory {
+
+    /**
+     * Check a finished hand was dealt from the deck committed to when it started. The deck
+     * is shuffled again from the revealed seed with the table's deck source, and has to
+     * open the commitment. Then the hand is dealt again, two hole cards to every seat with
+     * chips one at a time starting left of the dealer, and a burn card before each street.
+     * Every shown hand and every street on the board has to match.
+     */
+    pub fn verify_deal(&self) -> Result<(), Box<Error>> {
+
+        let mismatch = |reason: String| Box::new(Errors::DealMismatch(reason)) as Box<Error>;
+
+        let commitment = self.commitment.as_ref().ok_or(mismatch(String::from("the hand has no commitment")))?;
+        let seed = self.seed.as_ref()
+            .and_then(|seed| seed_from_hex(seed))
+            .ok_or(mismatch(String::from("the hand has no revealed seed")))?;
+
+        let mut deck = self.config.shuffle.source().deck(seed);
+        if commit(seed, &deck) != *commitment {
+            return Err(mismatch(String::from("the seed does not open the commitment")));
+        }
+
+        let seats = self.stacks.len();
+        let dealer_idx = self.dealer as usize - 1;
+        let mut hole_cards = vec![Vec::new(); seats];
+        for _ in 0..2 {
+            for offset in 1..seats + 1 {
+                let idx = (dealer_idx + offset) % seats;
+                if !self.stacks[idx].is_zero() {
+                    hole_cards[idx].push(deck.deal()?);
+                }
+            }
+        }
+
+        for (i, shown) in self.hole_cards.iter().enumerate() {
+            if !shown.is_empty() && *shown != hole_cards[i] {
+                return Err(mismatch(format!("seat {} was dealt other cards", i + 1)));
+            }
+        }
+
+        let mut board = Vec::new();
+        for event in self.events.iter() {
+            if let HandEvent::StreetDealt { street, ref cards } = *event {
+                deck.burn()?;
+                if deck.deal_many(cards.len())? != *cards {
+                    return Err(mismatch(format!("the {:?} is not the next card off the deck", street)));
+                }
+                board.extend(cards.iter().cloned());
+            }
+        }
+        if board != self.board {
+            return Err(mismatch(String::from("the board is not the one dealt")));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::{Shuffle, TableConfig};
+    use events::Blind;
+    use {Card, Chips, Street};
+
+    fn card(suit: u8, rank: u8) -> Card {
+        Card { suit: suit, rank: rank }
+    }
+
+    // Heads-up from a stacked deck: player 2 gets the first and third card, player 1 checks down with the second and fourth
+    fn history() -> HandHistory {
+        let stacked: Vec<Card> = (0..13).map(|rank| card(1, rank)).collect();
+        let mut config = TableConfig::default();
+        config.shuffle = Shuffle::Stacked { cards: stacked.clone() };
+        let deck = config.shuffle.source().deck(5);
+
+        HandHistory {
+            hand_number: 1,
+            config: config,
+            dealer: 1,
+            stacks: vec![Chips(64), Chips(64)],
+            hole_cards: vec![vec![stacked[1].clone(), stacked[3].clone()], Vec::new()],
+            board: stacked[5..8].to_vec(),
+            events: vec![
+                HandEvent::HandStarted { hand_number: 1, dealer: 1, stacks: vec![Chips(64), Chips(64)] },
+                HandEvent::BlindPosted { player: 1, blind: Blind::Small, amount: Chips(1) },
+                HandEvent::BlindPosted { player: 2, blind: Blind::Big, amount: Chips(2) },
+                HandEvent::Called { player: 1, to: Chips(2), all_in: false },
+                HandEvent::Checked { player: 2 },
+                HandEvent::StreetDealt { street: Street::Flop, cards: stacked[5..8].to_vec() },
+                HandEvent::Bet { player: 2, to: Chips(2), all_in: false },
+                HandEvent::Folded { player: 1 },
+                HandEvent::UncalledBetReturned { player: 2, amount: Chips(2) },
+                HandEvent::PotAwarded { pot: 0, player: 2, amount: Chips(4), ranking: None },
+                HandEvent::HandEnded
+            ],
+            commitment: Some(commit(5, &deck)),
+            seed: Some(seed_to_hex(5))
+        }
+    }
+
+    #[test]
+    fn seeds_are_hex() {
+        assert_eq!(seed_to_hex(255), "000000000000000000000000000000ff");
+        assert_eq!(seed_from_hex(&seed_to_hex(u128::max_value())), Some(u128::max_value()));
+        assert_eq!(seed_from_hex("ff"), None);
+    }
+
+    #[test]
+    fn commits_to_the_seed_and_the_deck() {
+        let deck = Deck::shuffled(5);
+        assert_eq!(commit(5, &deck).len(), 64);
+        assert_eq!(commit(5, &deck), commit(5, &Deck::shuffled(5)));
+        assert_ne!(commit(5, &deck), commit(6, &deck));
+        assert_ne!(commit(5, &deck), commit(5, &Deck::shuffled(6)));
+    }
+
+    #[test]
+    fn verifies_the_dealt_cards() {
+        assert!(history().verify_deal().is_ok());
+
+        let mut other_seed = history();
+        other_seed.seed = Some(seed_to_hex(6));
+        assert_eq!(other_seed.verify_deal().unwrap_err().to_string(),
+            Errors::DealMismatch(String::from("the seed does not open the commitment")).to_string());
+
+        let mut other_hole_cards = history();
+        other_hole_cards.hole_cards[0].reverse();
+        assert_eq!(other_hole_cards.verify_deal().unwrap_err().to_string(),
+            Errors::DealMismatch(String::from("seat 1 was dealt other cards")).to_string());
+
+        let mut unrevealed = history();
+        unrevealed.seed = None;
+        assert!(unrevealed.verify_deal().is_err());
+    }
+}
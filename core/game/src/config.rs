@@ -0,0 +1,271 @@
+/**
+ * Module that holds the rules a table is created with
+ */
+
+use serde_json;
+use std::error::Error;
+use std::sync::Mutex;
+use betting::BettingStructure;
+use chips::Chips;
+use deck::{DeckSource, SeededShuffle, StackedDeck, NUM_DECK_SUITS, NUM_DECK_VALUES};
+use {Card, Errors};
+
+pub const MIN_PLAYERS: u16 = 2;
+pub const MAX_PLAYERS: u16 = 10;
+
+const NUM_SEATS: u16 = 2;
+const STARTING_CHIPS: u64 = 64;
+const STANDARD_BET_SIZE: u64 = 2;
+const SMALL_BLIND: u64 = STANDARD_BET_SIZE / 2;
+const BIG_BLIND: u64 = STANDARD_BET_SIZE;
+const HAND_DISPLAY_MOVES: u16 = 4;
+
+/// Bets posted before any cards are dealt.
+/// With big_blind_ante, the big blind posts the ante for the whole table instead of every player.
+/// With straddle, the first player to act preflop may post twice the big blind and act last.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ForcedBets {
+    pub small_blind: Chips,
+    pub big_blind: Chips,
+    pub ante: Chips,
+    pub big_blind_ante: bool,
+    pub straddle: bool
+}
+
+impl Default for ForcedBets {
+    fn default() -> Self {
+        ForcedBets {
+            small_blind: Chips(SMALL_BLIND),
+            big_blind: Chips(BIG_BLIND),
+            ante: Chips::zero(),
+            big_blind_ante: false,
+            straddle: false
+        }
+    }
+}
+
+/// How the button moves when players bust.
+/// A moving button goes to the next player with chips and the blinds follow it,
+/// so a player can skip a blind. A dead button follows the big blind instead: the big
+/// blind always moves to the next player with chips, even if that leaves the button or
+/// the small blind on an empty seat.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ButtonRule {
+    Moving,
+    Dead
+}
+
+impl Default for ButtonRule {
+    fn default() -> Self {
+        ButtonRule::Moving
+    }
+}
+
+/// What the table does once a hand is paid out. The next hand is always dealt
+/// straight away, so nobody has to act between hands. With Display, the result and
+/// the board of the finished hand stay in the state for that many moves of the next
+/// hand, so clients can keep showing them. The wait is counted in moves and not in
+/// blocks, the game only sees the moves, and there is nothing for a player to skip.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum HandTransition {
+    Immediate,
+    Display { moves: u16 }
+}
+
+impl Default for HandTransition {
+    fn default() -> Self {
+        HandTransition::Display { moves: HAND_DISPLAY_MOVES }
+    }
+}
+
+/// Where the cards come from, see the deck sources. Seeded is the normal game, its seed
+/// already mixes the secret entropy of every player. Stacked deals the listed cards first
+/// in every hand and is only allowed in a scenario.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Shuffle {
+    Seeded,
+    Stacked { cards: Vec<Card> }
+}
+
+impl Default for Shuffle {
+    fn default() -> Self {
+        Shuffle::Seeded
+    }
+}
+
+impl Shuffle {
+    pub fn source(&self) -> Box<DeckSource> {
+        match self {
+            Shuffle::Seeded => Box::new(SeededShuffle),
+            Shuffle::Stacked { cards } => Box::new(StackedDeck { cards: cards.clone() })
+        }
+    }
+}
+
+/// Rules for one table, chosen when the game is created.
+/// The bet unit is the smallest opening bet in no-limit and pot-limit games, and has to be the
+/// big blind, so the smallest raise is the same before and after the flop.
+/// Missing fields take their default value.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct TableConfig {
+    pub num_seats: u16,
+    pub starting_chips: Chips,
+    pub bet_unit: Chips,
+    pub betting_structure: BettingStructure,
+    pub forced_bets: ForcedBets,
+    pub button_rule: ButtonRule,
+    pub hand_transition: HandTransition,
+    pub shuffle: Shuffle
+}
+
+impl Default for TableConfig {
+    fn default() -> Self {
+        TableConfig {
+            num_seats: NUM_SEATS,
+            starting_chips: Chips(STARTING_CHIPS),
+            bet_unit: Chips(STANDARD_BET_SIZE),
+            betting_structure: BettingStructure::NoLimit,
+            forced_bets: ForcedBets::default(),
+            button_rule: ButtonRule::default(),
+            hand_transition: HandTransition::default(),
+            shuffle: Shuffle::default()
+        }
+    }
+}
+
+impl TableConfig {
+
+    // Parse a JSON encoded config, as sent to the contract. No bytes means the default table.
+    pub fn from_bytes(bytes: &[u8]) -> Result<TableConfig, Box<Error>> {
+        if bytes.is_empty() {
+            return Ok(TableConfig::default());
+        }
+
+        let config: TableConfig = serde_json::from_slice(bytes)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("Could not serialize table config")
+    }
+
+    // Check a table players can create, nobody gets to choose the order of the deck
+    pub fn validate(&self) -> Result<(), Box<Error>> {
+
+        if let Shuffle::Stacked { .. } = self.shuffle {
+            return Err(Box::new(Errors::InvalidTableConfig("a stacked deck can only be used in a scenario")));
+        }
+
+        self.validate_rules()
+    }
+
+    // Check the rules hold together, a scenario may also stack the deck
+    pub fn validate_rules(&self) -> Result<(), Box<Error>> {
+
+        if self.num_seats < MIN_PLAYERS || self.num_seats > MAX_PLAYERS {
+            return Err(Box::new(Errors::InvalidTableSize(self.num_seats)));
+        }
+
+        if self.starting_chips.is_zero() || self.bet_unit.is_zero() {
+            return Err(Box::new(Errors::InvalidTableConfig("stacks and the bet unit must be positive")));
+        }
+
+        if self.forced_bets.big_blind < self.forced_bets.small_blind {
+            return Err(Box::new(Errors::InvalidTableConfig("the big blind cannot be smaller than the small blind")));
+        }
+
+        match self.betting_structure {
+            BettingStructure::FixedLimit { .. } => (),
+            _ => if self.bet_unit != self.forced_bets.big_blind {
+                return Err(Box::new(Errors::InvalidTableConfig("the bet unit has to be the big blind")));
+            }
+        }
+
+        if let BettingStructure::FixedLimit { small_bet, big_bet, max_bets } = self.betting_structure {
+            if small_bet.is_zero() || big_bet < small_bet || max_bets == 0 {
+                return Err(Box::new(Errors::InvalidTableConfig("fixed-limit needs a small bet, a larger big bet and a raise cap")));
+            }
+        }
+
+        if let Shuffle::Stacked { ref cards } = self.shuffle {
+            for (i, card) in cards.iter().enumerate() {
+                if card.suit >= NUM_DECK_SUITS || card.rank >= NUM_DECK_VALUES || cards[..i].contains(card) {
+                    return Err(Box::new(Errors::InvalidTableConfig("a stacked deck must hold every card at most once")));
+                }
+            }
+        }
+
+        // Every chip at the table has to fit in a single pot
+        if self.starting_chips.checked_mul(self.num_seats as u64).is_none() {
+            return Err(Box::new(Errors::InvalidTableConfig("the stacks are too large")));
+        }
+
+        Ok(())
+    }
+}
+
+lazy_static! {
+    static ref TABLE_CONFIG: Mutex<TableConfig> = Mutex::new(TableConfig::default());
+}
+
+/**
+ * Set the config used by the next game created, Flow::initial_state reads it.
+ */
+pub fn set_table_config(config: TableConfig) -> Result<(), Box<Error>> {
+    config.validate()?;
+    *TABLE_CONFIG.lock().expect("Table config lock poisoned") = config;
+    Ok(())
+}
+
+pub fn table_config() -> TableConfig {
+    TABLE_CONFIG.lock().expect("Table config lock poisoned").clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let config = TableConfig::from_bytes(br#"{"num_seats": 6, "forced_bets": {"ante": 1}}"#).unwrap();
+        assert_eq!(config.num_seats, 6);
+        assert_eq!(config.starting_chips, Chips(STARTING_CHIPS));
+        assert_eq!(config.forced_bets.big_blind, Chips(BIG_BLIND));
+        assert_eq!(config.forced_bets.ante, Chips(1));
+        assert_eq!(config.button_rule, ButtonRule::Moving);
+        assert_eq!(config.hand_transition, HandTransition::Display { moves: HAND_DISPLAY_MOVES });
+        assert_eq!(config.shuffle, Shuffle::Seeded);
+    }
+
+    #[test]
+    fn rejects_invalid_tables() {
+        assert!(TableConfig::from_bytes(br#"{"num_seats": 11}"#).is_err());
+        assert!(TableConfig::from_bytes(br#"{"forced_bets": {"small_blind": 4, "big_blind": 2}}"#).is_err());
+        assert_eq!(TableConfig::from_bytes(br#"{"bet_unit": 2, "forced_bets": {"small_blind": 5, "big_blind": 10}}"#).unwrap_err().to_string(),
+            Errors::InvalidTableConfig("the bet unit has to be the big blind").to_string());
+        assert!(TableConfig::from_bytes(br#"{"num_seats": 3, "starting_chips": 9000000000000000000}"#).is_err());
+    }
+
+    #[test]
+    fn only_scenarios_stack_the_deck() {
+        let stacked = TableConfig::from_bytes(br#"{"shuffle": {"Stacked": {"cards": [{"suit": 0, "rank": 1}]}}}"#);
+        assert_eq!(stacked.unwrap_err().to_string(),
+            Errors::InvalidTableConfig("a stacked deck can only be used in a scenario").to_string());
+
+        let config: TableConfig = serde_json::from_slice(br#"{"shuffle": {"Stacked": {"cards": [{"suit": 0, "rank": 1}]}}}"#).unwrap();
+        assert!(config.validate_rules().is_ok());
+        assert!(set_table_config(config).is_err());
+
+        let twice: TableConfig = serde_json::from_slice(br#"{"shuffle": {"Stacked": {"cards": [{"suit": 0, "rank": 1}, {"suit": 0, "rank": 1}]}}}"#).unwrap();
+        assert!(twice.validate_rules().is_err());
+    }
+
+    #[test]
+    fn big_stacks_fit() {
+        let config = TableConfig::from_bytes(br#"{"starting_chips": 5000000000, "bet_unit": 100000, "forced_bets": {"small_blind": 50000, "big_blind": 100000}}"#).unwrap();
+        assert_eq!(config.starting_chips, Chips(5_000_000_000));
+    }
+}
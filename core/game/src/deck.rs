@@ -0,0 +1,192 @@
+/**
+ * Module that shuffles and deals the cards for a hand
+ */
+
+use std::error::Error;
+use rand::{Rng, SeedableRng, ChaChaRng};
+use {Card, Errors};
+
+pub const NUM_DECK_SUITS: u8 = 4;
+pub const NUM_DECK_VALUES: u8 = 13;
+
+/// The cards left to deal this hand, in dealing order, top of the deck last.
+/// A deck is shuffled once when the hand starts and cards only ever leave it,
+/// so no card can be dealt twice.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Deck {
+    cards: Vec<Card>,
+    burned: Vec<Card>
+}
+
+impl Deck {
+
+    // All 52 cards, ordered by suit then rank
+    pub fn new() -> Deck {
+
+        let mut cards = Vec::new();
+        for suit in 0..NUM_DECK_SUITS {
+            for rank in 0..NUM_DECK_VALUES {
+                cards.push(Card {
+                    suit: suit,
+                    rank: rank
+                });
+            }
+        }
+
+        Deck {
+            cards: cards,
+            burned: Vec::new()
+        }
+    }
+
+    /**
+     * A new deck shuffled with Fisher-Yates, drawing every swap from one
+     * ChaCha stream seeded for this hand.
+     */
+    pub fn shuffled(seed: u128) -> Deck {
+
+        let mut seed_arr = [0 as u8; 32];
+        seed_arr[..16].copy_from_slice(&seed.to_le_bytes());
+
+        let mut deck = Deck::new();
+        deck.shuffle(seed_arr);
+        deck
+    }
+
+    /**
+     * A deck that deals cards in the order given, first card first, followed by the rest
+     * of the deck in the order of Deck::new. Hole cards are dealt one at a time starting
+     * left of the dealer, and a card is burned before each street.
+     */
+    pub fn stacked(cards: &Vec<Card>) -> Deck {
+
+        let mut deck = Deck::new();
+        deck.remove(cards);
+        deck.cards.reverse();
+        deck.cards.extend(cards.iter().rev().cloned());
+        deck
+    }
+
+    fn shuffle(&mut self, seed_arr: [u8; 32]) {
+
+        let mut rng = ChaChaRng::from_seed(seed_arr);
+        for i in (1..self.cards.len()).rev() {
+            let j = rng.gen_range(0, i + 1);
+            self.cards.swap(i, j);
+        }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.cards.len()
+    }
+
+    // The cards left, in the order they will be dealt
+    pub fn dealing_order(&self) -> Vec<Card> {
+        self.cards.iter().rev().cloned().collect()
+    }
+
+    // Take cards that are already in play out of the deck, keeping the order of the rest
+    pub fn remove(&mut self, cards: &Vec<Card>) {
+        self.cards.retain(|card| !cards.contains(card));
+    }
+
+    // Cards burned so far this hand, in the order they were burned
+    pub fn burned(&self) -> &Vec<Card> {
+        &self.burned
+    }
+
+    pub fn deal(&mut self) -> Result<Card, Box<Error>> {
+        self.cards.pop().ok_or(Box::new(Errors::DeckExhausted))
+    }
+
+    pub fn deal_many(&mut self, num_cards: usize) -> Result<Vec<Card>, Box<Error>> {
+
+        if num_cards > self.cards.len() {
+            return Err(Box::new(Errors::DeckExhausted));
+        }
+
+        let mut dealt = Vec::new();
+        for _ in 0..num_cards {
+            dealt.push(self.deal()?);
+        }
+        Ok(dealt)
+    }
+
+    // Discard the top card face down, before each community street
+    pub fn burn(&mut self) -> Result<(), Box<Error>> {
+        let card = self.deal()?;
+        self.burned.push(card);
+        Ok(())
+    }
+}
+
+/// Where the deck for every hand comes from. The engine asks the table's source for a
+/// new deck each time a hand is dealt, passing the hand seed from the table's entropy.
+pub trait DeckSource {
+    fn deck(&self, seed: u128) -> Deck;
+}
+
+/// The default source, a Fisher-Yates shuffle from a ChaCha stream seeded for the hand.
+pub struct SeededShuffle;
+
+impl DeckSource for SeededShuffle {
+    fn deck(&self, seed: u128) -> Deck {
+        Deck::shuffled(seed)
+    }
+}
+
+/// The same cards in the same order every hand, whatever the seed. For tests and for
+/// setting up a hand by hand.
+pub struct StackedDeck {
+    pub cards: Vec<Card>
+}
+
+impl DeckSource for StackedDeck {
+    fn deck(&self, _seed: u128) -> Deck {
+        Deck::stacked(&self.cards)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn deals_every_card_once() {
+        let mut deck = Deck::shuffled(7);
+        deck.burn().unwrap();
+
+        let mut seen: HashSet<(u8, u8)> = deck.burned().iter().map(|card| (card.suit, card.rank)).collect();
+        for card in deck.deal_many(51).unwrap() {
+            assert!(seen.insert((card.suit, card.rank)));
+        }
+
+        assert_eq!(seen.len(), 52);
+        assert!(deck.deal().is_err());
+    }
+
+    #[test]
+    fn shuffle_depends_only_on_the_seed() {
+        let mut deck = Deck::shuffled(42);
+        let mut same_deck = Deck::shuffled(42);
+        let mut other_deck = Deck::shuffled(43);
+
+        let dealt: Vec<(u8, u8)> = deck.deal_many(52).unwrap().iter().map(|card| (card.suit, card.rank)).collect();
+        let same: Vec<(u8, u8)> = same_deck.deal_many(52).unwrap().iter().map(|card| (card.suit, card.rank)).collect();
+        let other: Vec<(u8, u8)> = other_deck.deal_many(52).unwrap().iter().map(|card| (card.suit, card.rank)).collect();
+
+        assert_eq!(dealt, same);
+        assert_ne!(dealt, other);
+    }
+
+    #[test]
+    fn stacked_deck_deals_in_order() {
+        let aces: Vec<Card> = (0..NUM_DECK_SUITS).map(|suit| Card { suit: suit, rank: 12 }).collect();
+        let mut deck = StackedDeck { cards: aces.clone() }.deck(42);
+
+        assert_eq!(deck.deal_many(4).unwrap(), aces);
+        assert_eq!(deck.deal().unwrap(), Card { suit: 0, rank: 0 });
+        assert_eq!(deck.remaining(), 47);
+    }
+}
@@ -0,0 +1,152 @@
+/**
+ * Module that turns the entropy players send when they get ready into the seed of every hand
+ */
+
+use serde_json;
+use std::error::Error;
+use std::sync::Mutex;
+use tiny_keccak::Keccak;
+
+// Keeps these hashes apart from any other keccak256 hash of the same bytes
+const HAND_SEED_DOMAIN: &[u8] = b"oasis-poker/hand-seed/v1";
+
+/// The entropy every player sent with ready() for one game, in the order they got ready.
+/// It is kept in the state of the game, so a replay deals the same hands, and never shown to a player.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TableEntropy {
+    pub game_id: u64,
+    pub contributions: Vec<Vec<u8>>
+}
+
+impl TableEntropy {
+
+    pub fn new(game_id: u64) -> TableEntropy {
+        TableEntropy {
+            game_id: game_id,
+            contributions: Vec::new()
+        }
+    }
+
+    // A game without players' entropy, like a local game, only has the seed it was created with
+    pub fn from_seed(seed: u128) -> TableEntropy {
+        TableEntropy {
+            game_id: 0,
+            contributions: vec![seed.to_le_bytes().to_vec()]
+        }
+    }
+
+    // Read the entropy stored for a game, no bytes means nobody is ready yet
+    pub fn from_bytes(game_id: u64, bytes: &[u8]) -> Result<TableEntropy, Box<Error>> {
+        if bytes.is_empty() {
+            return Ok(TableEntropy::new(game_id));
+        }
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("Could not serialize table entropy")
+    }
+
+    pub fn add(&mut self, entropy: Vec<u8>) {
+        self.contributions.push(entropy);
+    }
+
+    /**
+     * The seed the deck of a hand is shuffled with. It is the first 16 bytes, read little
+     * endian, of
+     *
+     *   keccak256(domain, game_id, hand_number, n, len_1, entropy_1, ..., len_n, entropy_n)
+     *
+     * where the domain is HAND_SEED_DOMAIN, every number is a little endian u64 and the
+     * entropy is taken in the order the players got ready. Each contribution is prefixed by
+     * its length, so no bytes can move from one player to the next without changing the
+     * hash. Knowing the seed of a hand needs every contribution, so a player who does not
+     * know the others' entropy can neither predict the deal nor pick one.
+     */
+    pub fn hand_seed(&self, hand_number: u32) -> u128 {
+
+        let mut keccak = Keccak::new_keccak256();
+        keccak.update(HAND_SEED_DOMAIN);
+        keccak.update(&self.game_id.to_le_bytes());
+        keccak.update(&(hand_number as u64).to_le_bytes());
+        keccak.update(&(self.contributions.len() as u64).to_le_bytes());
+        for entropy in self.contributions.iter() {
+            keccak.update(&(entropy.len() as u64).to_le_bytes());
+            keccak.update(entropy);
+        }
+
+        let mut hash = [0 as u8; 32];
+        keccak.finalize(&mut hash);
+
+        let mut seed = [0 as u8; 16];
+        seed.copy_from_slice(&hash[..16]);
+        u128::from_le_bytes(seed)
+    }
+}
+
+lazy_static! {
+    static ref TABLE_ENTROPY: Mutex<Option<TableEntropy>> = Mutex::new(None);
+}
+
+/**
+ * Set the entropy of the next game created, Flow::initial_state reads it like the table config.
+ */
+pub fn set_table_entropy(entropy: Option<TableEntropy>) {
+    *TABLE_ENTROPY.lock().expect("Table entropy lock poisoned") = entropy;
+}
+
+pub fn table_entropy() -> Option<TableEntropy> {
+    TABLE_ENTROPY.lock().expect("Table entropy lock poisoned").clone()
+        .and_then(|entropy| if entropy.contributions.is_empty() { None } else { Some(entropy) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiny_keccak::keccak256;
+
+    fn entropy(game_id: u64, contributions: Vec<&[u8]>) -> TableEntropy {
+        TableEntropy {
+            game_id: game_id,
+            contributions: contributions.iter().map(|bytes| bytes.to_vec()).collect()
+        }
+    }
+
+    #[test]
+    fn follows_the_documented_derivation() {
+        let mut preimage = HAND_SEED_DOMAIN.to_vec();
+        preimage.extend_from_slice(&[9, 0, 0, 0, 0, 0, 0, 0]);
+        preimage.extend_from_slice(&[3, 0, 0, 0, 0, 0, 0, 0]);
+        preimage.extend_from_slice(&[2, 0, 0, 0, 0, 0, 0, 0]);
+        preimage.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 0, 0xaa]);
+        preimage.extend_from_slice(&[2, 0, 0, 0, 0, 0, 0, 0, 0xbb, 0xcc]);
+
+        let mut seed = [0 as u8; 16];
+        seed.copy_from_slice(&keccak256(&preimage)[..16]);
+        assert_eq!(entropy(9, vec![&[0xaa], &[0xbb, 0xcc]]).hand_seed(3), u128::from_le_bytes(seed));
+    }
+
+    #[test]
+    fn every_input_changes_the_seed() {
+        let table = entropy(9, vec![b"alice", b"bob"]);
+        let seed = table.hand_seed(1);
+
+        assert_eq!(seed, entropy(9, vec![b"alice", b"bob"]).hand_seed(1));
+        assert_ne!(seed, table.hand_seed(2));
+        assert_ne!(seed, entropy(10, vec![b"alice", b"bob"]).hand_seed(1));
+        assert_ne!(seed, entropy(9, vec![b"alice", b"bot"]).hand_seed(1));
+        assert_ne!(seed, entropy(9, vec![b"alicex", b"bob"]).hand_seed(1));
+        assert_ne!(seed, entropy(9, vec![b"alic", b"ebob"]).hand_seed(1));
+        assert_ne!(seed, entropy(9, vec![b"bob", b"alice"]).hand_seed(1));
+        assert_ne!(seed, entropy(9, vec![b"alice", b"bob", b""]).hand_seed(1));
+    }
+
+    #[test]
+    fn stored_entropy_round_trips() {
+        let mut table = TableEntropy::from_bytes(4, &[]).unwrap();
+        assert_eq!(table, TableEntropy::new(4));
+
+        table.add(b"alice".to_vec());
+        assert_eq!(TableEntropy::from_bytes(4, &table.to_bytes()).unwrap(), table);
+    }
+}
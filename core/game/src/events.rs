@@ -0,0 +1,211 @@
+/**
+ * Module that records what happens in a hand as typed events
+ */
+
+use chips::Chips;
+use pots;
+use resolver;
+use {Card, CardRanking, Street};
+
+/// Which forced or voluntary blind a player posted.
+/// A missed blind is the big blind owed by a player the blinds passed over.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Blind {
+    Small,
+    Big,
+    Missed,
+    Straddle
+}
+
+/// One thing that happened in a hand, in the order it happened.
+/// Players are seats 1 to num_players. Like the moves, bet, raise and call amounts
+/// are the total the player has on the table this betting round.
+/// Once the betting is over, a bet nobody matched goes back to its player before any pot
+/// is awarded. A pot is awarded with the winning ranking, or none when everyone else folded.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type")]
+pub enum HandEvent {
+    HandStarted { hand_number: u32, dealer: u16, stacks: Vec<Chips> },
+    BlindPosted { player: u16, blind: Blind, amount: Chips },
+    AntePosted { player: u16, amount: Chips },
+    Folded { player: u16 },
+    Checked { player: u16 },
+    Called { player: u16, to: Chips, all_in: bool },
+    Bet { player: u16, to: Chips, all_in: bool },
+    Raised { player: u16, to: Chips, all_in: bool },
+    StreetDealt { street: Street, cards: Vec<Card> },
+    Shown { player: u16, cards: Vec<Card> },
+    Mucked { player: u16 },
+    UncalledBetReturned { player: u16, amount: Chips },
+    PotAwarded { pot: usize, player: u16, amount: Chips, ranking: Option<CardRanking> },
+    HandEnded
+}
+
+impl HandEvent {
+
+    // Events a player made happen, as opposed to the dealer
+    fn is_move(&self) -> bool {
+        match *self {
+            HandEvent::StreetDealt { .. } | HandEvent::UncalledBetReturned { .. } | HandEvent::PotAwarded { .. } |
+            HandEvent::HandEnded => false,
+            _ => true
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match *self {
+            HandEvent::HandStarted { .. } => String::from("New Hand"),
+            HandEvent::BlindPosted { blind, amount, .. } => match blind {
+                Blind::Small => format!("Small Blind {}", amount),
+                Blind::Big => format!("Big Blind {}", amount),
+                Blind::Missed => format!("Missed Blind {}", amount),
+                Blind::Straddle => format!("Straddle {}", amount)
+            },
+            HandEvent::AntePosted { amount, .. } => format!("Ante {}", amount),
+            HandEvent::Folded { .. } => String::from("Fold"),
+            HandEvent::Checked { .. } => String::from("Check"),
+            HandEvent::Called { to, all_in, .. } => match all_in {
+                true => format!("All In {}", to),
+                false => format!("Call {}", to)
+            },
+            HandEvent::Bet { to, all_in, .. } => match all_in {
+                true => format!("All In {}", to),
+                false => format!("Bet {}", to)
+            },
+            HandEvent::Raised { to, all_in, .. } => match all_in {
+                true => format!("All In {}", to),
+                false => format!("Raise {}", to)
+            },
+            HandEvent::StreetDealt { street, ref cards } => format!("{:?} [{}]", street, cards_to_string(cards)),
+            HandEvent::Shown { ref cards, .. } => format!("Show {}", cards_to_string(cards)),
+            HandEvent::Mucked { .. } => String::from("Muck"),
+            HandEvent::UncalledBetReturned { player, amount } => format!("Player {} takes back {}", player, amount),
+            HandEvent::PotAwarded { player, amount, .. } => format!("Player {} wins {}", player, amount),
+            HandEvent::HandEnded => String::from("HAND OVER")
+        }
+    }
+}
+
+pub fn cards_to_string(cards: &Vec<Card>) -> String {
+    let names: Vec<String> = cards.iter().map(resolver::card_to_string).collect();
+    names.join(" ")
+}
+
+/**
+ * The last move of the hand as shown to players, marked when it ended the hand.
+ */
+pub fn describe_last_move(events: &Vec<HandEvent>) -> String {
+
+    let last_move = match events.iter().rev().find(|event| event.is_move()) {
+        Some(event) => event.describe(),
+        None => String::from("")
+    };
+
+    match events.last() {
+        Some(&HandEvent::HandEnded) => format!("{} - HAND OVER", last_move),
+        _ => last_move
+    }
+}
+
+/**
+ * The result of a finished hand as shown to players: who won each pot and with what,
+ * and the hands that were shown without winning anything.
+ */
+pub fn describe_result(events: &Vec<HandEvent>) -> String {
+
+    let mut pots: Vec<(usize, Vec<u16>, Option<CardRanking>)> = Vec::new();
+    let mut shown: Vec<(u16, &Vec<Card>)> = Vec::new();
+
+    for event in events {
+        match *event {
+            HandEvent::PotAwarded { pot, player, ref ranking, .. } => {
+                match pots.iter().position(|awarded| awarded.0 == pot) {
+                    Some(idx) => pots[idx].1.push(player),
+                    None => pots.push((pot, vec![player], ranking.clone()))
+                }
+            },
+            HandEvent::Shown { player, ref cards } => shown.push((player, cards)),
+            _ => ()
+        }
+    }
+
+    let cards_of = |player: u16| shown.iter()
+        .find(|&&(shown_player, _)| shown_player == player)
+        .map(|&(_, cards)| cards_to_string(cards))
+        .unwrap_or(String::from(""));
+
+    let mut results = Vec::new();
+
+    for &(pot, ref winners, ref ranking) in &pots {
+
+        let hand_label = match *ranking {
+            Some(ref ranking) => resolver::hand_to_string(&ranking.hand),
+            None => {
+                // Won by fold, every pot goes to the same player
+                results.push(format!("Player {} wins by fold", winners[0]));
+                break;
+            }
+        };
+
+        let pot_label = match pots.len() {
+            1 => String::from("the pot"),
+            _ => pots::pot_to_string(pot)
+        };
+
+        if winners.len() == 1 {
+            if pots.len() == 1 {
+                results.push(format!("Player {} wins with {} [{}]", winners[0], hand_label, cards_of(winners[0])));
+            } else {
+                results.push(format!("Player {} wins {} with {} [{}]", winners[0], pot_label, hand_label, cards_of(winners[0])));
+            }
+        } else {
+            let names: Vec<String> = winners.iter().map(|player| player.to_string()).collect();
+            results.push(format!("Players {} split {} with {}", names.join(", "), pot_label, hand_label));
+        }
+    }
+
+    // Shown hands that won nothing are part of the result too
+    for &(player, cards) in &shown {
+        if !pots.iter().any(|awarded| awarded.1.contains(&player)) {
+            results.push(format!("Player {} shows [{}]", player, cards_to_string(cards)));
+        }
+    }
+
+    results.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flush() -> Option<CardRanking> {
+        Some(CardRanking { player: 1, hand: 5, tiebreak: vec![12, 9, 7, 4, 2] })
+    }
+
+    #[test]
+    fn describes_the_result_from_the_events() {
+        let ace_king = vec![Card { suit: 2, rank: 12 }, Card { suit: 2, rank: 11 }];
+        let events = vec![
+            HandEvent::Raised { player: 2, to: Chips(8), all_in: false },
+            HandEvent::Shown { player: 2, cards: ace_king },
+            HandEvent::Shown { player: 3, cards: vec![Card { suit: 0, rank: 0 }, Card { suit: 1, rank: 0 }] },
+            HandEvent::PotAwarded { pot: 0, player: 2, amount: Chips(16), ranking: flush() },
+            HandEvent::HandEnded
+        ];
+
+        assert_eq!(describe_result(&events), "Player 2 wins with Flush [Ah Kh], Player 3 shows [2d 2c]");
+        assert_eq!(describe_last_move(&events), "Show 2d 2c - HAND OVER");
+    }
+
+    #[test]
+    fn describes_a_split_and_a_fold() {
+        let split = vec![
+            HandEvent::PotAwarded { pot: 0, player: 1, amount: Chips(5), ranking: flush() },
+            HandEvent::PotAwarded { pot: 0, player: 2, amount: Chips(4), ranking: flush() }
+        ];
+        assert_eq!(describe_result(&split), "Players 1, 2 split the pot with Flush");
+
+        let fold = vec![HandEvent::PotAwarded { pot: 0, player: 3, amount: Chips(3), ranking: None }];
+        assert_eq!(describe_result(&fold), "Player 3 wins by fold");
+    }
+}
@@ -0,0 +1,602 @@
+/**
+ * Module that archives finished hands and writes them out as PokerStars hand histories
+ */
+
+use std::error::Error;
+use oasis_game_core::{Context, UserState};
+use actions::PokerAction;
+use betting::BettingStructure;
+use chips::Chips;
+use config::{Shuffle, TableConfig};
+use deck::Deck;
+use entropy::TableEntropy;
+use events::{self, Blind, HandEvent};
+use pots;
+use replay;
+use resolver;
+use scenario::{Scenario, Seat};
+use {Card, Errors, State, Street, begin_turn};
+
+/// Everything public about a finished hand: who sat where with how many chips,
+/// the board, the hole cards shown at the showdown and every event in order.
+/// Seats are 1 to num_players, hole cards that were never shown are left empty.
+/// The commitment to the deck and the seed it was shuffled with let anyone check the deal.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HandHistory {
+    pub hand_number: u32,
+    pub config: TableConfig,
+    pub dealer: u16,
+    pub stacks: Vec<Chips>,
+    pub hole_cards: Vec<Vec<Card>>,
+    pub board: Vec<Card>,
+    pub events: Vec<HandEvent>,
+    #[serde(default)]
+    pub commitment: Option<String>,
+    #[serde(default)]
+    pub seed: Option<String>
+}
+
+impl HandHistory {
+
+    // Archive the hand that just ended, stacks are taken from before the blinds
+    pub fn record(state: &State) -> HandHistory {
+
+        let stacks = match state.events.first() {
+            Some(&HandEvent::HandStarted { ref stacks, .. }) => stacks.clone(),
+            _ => state.chips.clone()
+        };
+
+        let hole_cards = state.hands.iter().zip(state.shown.iter())
+            .map(|(hand, shown)| if *shown { hand.clone() } else { Vec::new() })
+            .collect();
+
+        HandHistory {
+            hand_number: state.hand_number,
+            config: state.config.clone(),
+            dealer: state.dealer,
+            stacks: stacks,
+            hole_cards: hole_cards,
+            board: state.card_table.clone(),
+            events: state.events.clone(),
+            commitment: state.deck_commitment.clone(),
+            seed: state.deck_seed.clone()
+        }
+    }
+
+    /**
+     * Write the hand in the PokerStars text format read by tracking software.
+     * The game keeps no clock, so the caller passes the time the hand was played,
+     * formatted like "2019/01/31 20:15:00 ET".
+     */
+    pub fn to_pokerstars(&self, table_name: &str, timestamp: &str) -> String {
+
+        let seats = self.stacks.len();
+        let mut lines = Vec::new();
+
+        let game = match self.config.betting_structure {
+            BettingStructure::NoLimit => format!("No Limit ({}/{})", self.config.forced_bets.small_blind, self.config.forced_bets.big_blind),
+            BettingStructure::PotLimit => format!("Pot Limit ({}/{})", self.config.forced_bets.small_blind, self.config.forced_bets.big_blind),
+            BettingStructure::FixedLimit { small_bet, big_bet, .. } => format!("Limit ({}/{})", small_bet, big_bet)
+        };
+        lines.push(format!("PokerStars Hand #{}: Hold'em {} - {}", self.hand_number, game, timestamp));
+        lines.push(format!("Table '{}' {}-max Seat #{} is the button", table_name, seats, self.dealer));
+
+        for (i, stack) in self.stacks.iter().enumerate() {
+            if !stack.is_zero() {
+                lines.push(format!("Seat {}: {} ({} in chips)", i + 1, name(i as u16 + 1), stack));
+            }
+        }
+
+        let mut on_table = vec![Chips::zero(); seats];
+        let mut contributions = vec![Chips::zero(); seats];
+        let mut folded_on: Vec<Option<Street>> = vec![None; seats];
+        let mut mucked = vec![false; seats];
+        let mut won = vec![Chips::zero(); seats];
+        let mut street = Street::Preflop;
+        let mut dealt = false;
+        let mut showdown = false;
+
+        let collected = collected(&self.events);
+        let num_pots = collected.iter().map(|&(pot, _, _)| pot + 1).max().unwrap_or(0);
+        let mut paid = false;
+
+        for event in &self.events {
+
+            // Hole cards are dealt once the forced bets are in
+            let forced = match *event {
+                HandEvent::HandStarted { .. } | HandEvent::BlindPosted { blind: Blind::Small, .. } |
+                HandEvent::BlindPosted { blind: Blind::Big, .. } | HandEvent::BlindPosted { blind: Blind::Missed, .. } |
+                HandEvent::AntePosted { .. } => true,
+                _ => false
+            };
+            if !forced && !dealt {
+                lines.push(String::from("*** HOLE CARDS ***"));
+                dealt = true;
+            }
+
+            match *event {
+
+                HandEvent::HandStarted { .. } => (),
+
+                HandEvent::BlindPosted { player, blind, amount } => {
+                    let idx = player as usize - 1;
+                    on_table[idx] = on_table[idx].saturating_add(amount);
+                    contributions[idx] = contributions[idx].saturating_add(amount);
+                    let posts = match blind {
+                        Blind::Small => "small blind",
+                        Blind::Big | Blind::Missed => "big blind",
+                        Blind::Straddle => "straddle"
+                    };
+                    lines.push(format!("{}: posts {} {}", name(player), posts, amount));
+                },
+
+                HandEvent::AntePosted { player, amount } => {
+                    let idx = player as usize - 1;
+                    contributions[idx] = contributions[idx].saturating_add(amount);
+                    lines.push(format!("{}: posts the ante {}", name(player), amount));
+                },
+
+                HandEvent::Folded { player } => {
+                    folded_on[player as usize - 1] = Some(street);
+                    lines.push(format!("{}: folds", name(player)));
+                },
+
+                HandEvent::Checked { player } => lines.push(format!("{}: checks", name(player))),
+
+                HandEvent::Called { player, to, all_in } => {
+                    let idx = player as usize - 1;
+                    let added = to.saturating_sub(on_table[idx]);
+                    on_table[idx] = to;
+                    contributions[idx] = contributions[idx].saturating_add(added);
+                    lines.push(format!("{}: calls {}{}", name(player), added, all_in_suffix(all_in)));
+                },
+
+                HandEvent::Bet { player, to, all_in } => {
+                    let idx = player as usize - 1;
+                    let added = to.saturating_sub(on_table[idx]);
+                    on_table[idx] = to;
+                    contributions[idx] = contributions[idx].saturating_add(added);
+                    lines.push(format!("{}: bets {}{}", name(player), added, all_in_suffix(all_in)));
+                },
+
+                HandEvent::Raised { player, to, all_in } => {
+                    let idx = player as usize - 1;
+                    let bet = on_table.iter().cloned().max().unwrap_or(Chips::zero());
+                    contributions[idx] = contributions[idx].saturating_add(to.saturating_sub(on_table[idx]));
+                    on_table[idx] = to;
+                    lines.push(format!("{}: raises {} to {}{}", name(player), to.saturating_sub(bet), to, all_in_suffix(all_in)));
+                },
+
+                HandEvent::StreetDealt { street: dealt_street, ref cards } => {
+                    street = dealt_street;
+                    on_table = vec![Chips::zero(); seats];
+
+                    let board_before = self.board.len().min(match dealt_street {
+                        Street::Turn => 3,
+                        Street::River => 4,
+                        _ => 0
+                    });
+                    let label = format!("{:?}", dealt_street).to_uppercase();
+                    match board_before {
+                        0 => lines.push(format!("*** {} *** [{}]", label, events::cards_to_string(cards))),
+                        _ => lines.push(format!("*** {} *** [{}] [{}]", label,
+                            events::cards_to_string(&self.board[..board_before].to_vec()), events::cards_to_string(cards)))
+                    }
+                },
+
+                HandEvent::UncalledBetReturned { player, amount } => {
+                    let idx = player as usize - 1;
+                    on_table[idx] = on_table[idx].saturating_sub(amount);
+                    contributions[idx] = contributions[idx].saturating_sub(amount);
+                    lines.push(format!("Uncalled bet ({}) returned to {}", amount, name(player)));
+                },
+
+                HandEvent::Shown { player, ref cards } => {
+                    if !showdown {
+                        lines.push(String::from("*** SHOW DOWN ***"));
+                        showdown = true;
+                    }
+                    lines.push(format!("{}: shows [{}] ({})", name(player), events::cards_to_string(cards), self.hand_label(cards)));
+                },
+
+                HandEvent::Mucked { player } => {
+                    mucked[player as usize - 1] = true;
+                    lines.push(format!("{}: mucks hand", name(player)));
+                },
+
+                HandEvent::PotAwarded { .. } => {
+                    if paid {
+                        continue;
+                    }
+
+                    for &(pot, player, amount) in &collected {
+                        let idx = player as usize - 1;
+                        won[idx] = won[idx].saturating_add(amount);
+                        let from = match num_pots {
+                            1 => String::from("pot"),
+                            _ => pots::pot_to_string(pot).replace("side pot ", "side pot-")
+                        };
+                        lines.push(format!("{} collected {} from {}", name(player), amount, from));
+                    }
+                    paid = true;
+                },
+
+                HandEvent::HandEnded => ()
+            }
+        }
+
+        // Summary
+        let total: Chips = contributions.iter().sum();
+        lines.push(String::from("*** SUMMARY ***"));
+        lines.push(format!("Total pot {} | Rake 0", total));
+        if !self.board.is_empty() {
+            lines.push(format!("Board [{}]", events::cards_to_string(&self.board)));
+        }
+
+        let (small_blind, big_blind) = blind_players(&self.events);
+        for i in 0..seats {
+
+            if self.stacks[i].is_zero() {
+                continue;
+            }
+
+            let player = i as u16 + 1;
+            // Heads-up the button is also the small blind
+            let mut position = String::new();
+            if player == self.dealer {
+                position.push_str(" (button)");
+            }
+            if Some(player) == small_blind {
+                position.push_str(" (small blind)");
+            } else if Some(player) == big_blind {
+                position.push_str(" (big blind)");
+            }
+
+            let outcome = if let Some(folded) = folded_on[i] {
+                match folded {
+                    Street::Preflop => String::from("folded before Flop"),
+                    street => format!("folded on the {:?}", street)
+                }
+            } else if mucked[i] {
+                String::from("mucked")
+            } else if !self.hole_cards[i].is_empty() {
+                let cards = events::cards_to_string(&self.hole_cards[i]);
+                match won[i].is_zero() {
+                    true => format!("showed [{}] and lost with {}", cards, self.hand_label(&self.hole_cards[i])),
+                    false => format!("showed [{}] and won ({}) with {}", cards, won[i], self.hand_label(&self.hole_cards[i]))
+                }
+            } else {
+                format!("collected ({})", won[i])
+            };
+
+            lines.push(format!("Seat {}: {}{} {}", player, name(player), position, outcome));
+        }
+
+        lines.join("\n")
+    }
+
+    /**
+     * The moves the players made, in order, as (player, action) to send to poker_move.
+     * Blinds, antes and the hands shown automatically at the showdown are not moves:
+     * the first player to show and every player who is all in show without being asked.
+     */
+    pub fn moves(&self) -> Vec<(u16, PokerAction)> {
+
+        let mut stacks = self.stacks.clone();
+        let mut on_table = vec![Chips::zero(); stacks.len()];
+        let mut showdown = false;
+        let mut moves = Vec::new();
+
+        for event in &self.events {
+            match *event {
+
+                HandEvent::BlindPosted { player, blind, amount } => {
+                    let idx = player as usize - 1;
+                    stacks[idx] = stacks[idx].saturating_sub(amount);
+                    on_table[idx] = on_table[idx].saturating_add(amount);
+                    if blind == Blind::Straddle {
+                        moves.push((player, PokerAction::Straddle));
+                    }
+                },
+
+                HandEvent::AntePosted { player, amount } => {
+                    let idx = player as usize - 1;
+                    stacks[idx] = stacks[idx].saturating_sub(amount);
+                },
+
+                HandEvent::Folded { player } => moves.push((player, PokerAction::Fold)),
+                HandEvent::Checked { player } => moves.push((player, PokerAction::Check)),
+
+                HandEvent::Called { player, to, .. } | HandEvent::Bet { player, to, .. } | HandEvent::Raised { player, to, .. } => {
+                    let idx = player as usize - 1;
+                    stacks[idx] = stacks[idx].saturating_sub(to.saturating_sub(on_table[idx]));
+                    on_table[idx] = to;
+
+                    // An all in can be short of a full raise, so it is replayed as an all in
+                    let action = match *event {
+                        HandEvent::Called { .. } => PokerAction::Call,
+                        HandEvent::Bet { all_in: true, .. } | HandEvent::Raised { all_in: true, .. } => PokerAction::AllIn,
+                        HandEvent::Bet { .. } => PokerAction::Bet { to: to },
+                        _ => PokerAction::Raise { to: to }
+                    };
+                    moves.push((player, action));
+                },
+
+                HandEvent::StreetDealt { .. } => on_table = vec![Chips::zero(); stacks.len()],
+
+                HandEvent::Shown { player, .. } => {
+                    if showdown && !stacks[player as usize - 1].is_zero() {
+                        moves.push((player, PokerAction::Show));
+                    }
+                    showdown = true;
+                },
+
+                HandEvent::Mucked { player } => moves.push((player, PokerAction::Muck)),
+
+                HandEvent::UncalledBetReturned { player, amount } => {
+                    let idx = player as usize - 1;
+                    stacks[idx] = stacks[idx].saturating_add(amount);
+                },
+
+                HandEvent::HandStarted { .. } | HandEvent::PotAwarded { .. } | HandEvent::HandEnded => ()
+            }
+        }
+
+        moves
+    }
+
+    /**
+     * Play the hand again through the engine and check every uncalled bet and every pot went
+     * where the history says it did. The table is set up preflop from the stacks, with the blinds and antes
+     * already posted, and the moves are sent to poker_move like on the game server. The
+     * deck is stacked so the board comes out as recorded, and players whose cards were
+     * never shown are dealt cards nobody else holds, they folded or mucked anyway.
+     */
+    pub fn check_result(&self) -> Result<(), Box<Error>> {
+
+        let seats = self.stacks.len();
+        // Blinds and antes are posted, a straddle is the first move of the hand
+        let mut bets = vec![Chips::zero(); seats];
+        let mut antes = vec![Chips::zero(); seats];
+        for event in &self.events {
+            match *event {
+                HandEvent::BlindPosted { player, blind, amount } if blind != Blind::Straddle => {
+                    bets[player as usize - 1] = bets[player as usize - 1].saturating_add(amount);
+                },
+                HandEvent::AntePosted { player, amount } => {
+                    antes[player as usize - 1] = antes[player as usize - 1].saturating_add(amount);
+                },
+                _ => ()
+            }
+        }
+
+        let known: Vec<Card> = self.hole_cards.iter().flat_map(|cards| cards.iter().cloned()).chain(self.board.iter().cloned()).collect();
+        let mut unknown = Deck::new().dealing_order().into_iter().filter(|card| !known.contains(card));
+        let mut deal_unknown = || unknown.next().ok_or(Box::new(Errors::DeckExhausted) as Box<Error>);
+
+        let mut players = Vec::new();
+        for i in 0..seats {
+            let hole_cards = match (self.hole_cards[i].len(), self.stacks[i].is_zero()) {
+                (2, _) | (_, true) => self.hole_cards[i].clone(),
+                _ => vec![deal_unknown()?, deal_unknown()?]
+            };
+            players.push(Seat {
+                stack: self.stacks[i].checked_sub(bets[i].saturating_add(antes[i]))
+                    .ok_or(Box::new(Errors::InvalidHandHistory(format!("seat {} posted more than its stack", i + 1))))?,
+                bet: bets[i],
+                invested: antes[i],
+                hole_cards: hole_cards,
+                folded: false
+            });
+        }
+
+        // Preflop the action starts left of the big blind
+        let big_blind = blind_players(&self.events).1.unwrap_or(self.dealer) as usize - 1;
+        let to_act = (1..seats + 1).map(|offset| (big_blind + offset) % seats)
+            .find(|&idx| !players[idx].stack.is_zero() && !players[idx].hole_cards.is_empty())
+            .ok_or(Box::new(Errors::InvalidHandHistory(String::from("nobody can act in the hand"))))?;
+
+        // A card is burned before each street
+        let mut stacked = Vec::new();
+        for (i, card) in self.board.iter().enumerate() {
+            if i == 0 || i >= 3 {
+                stacked.push(deal_unknown()?);
+            }
+            stacked.push(card.clone());
+        }
+
+        let mut config = self.config.clone();
+        config.shuffle = Shuffle::Stacked { cards: stacked };
+        let scenario = Scenario {
+            config: config,
+            seats: players,
+            dealer: self.dealer,
+            street: Street::Preflop,
+            board: Vec::new(),
+            pot: Chips::checked_sum(antes.iter()).ok_or(Box::new(Errors::ChipOverflow))?,
+            to_act: to_act as u16 + 1
+        };
+
+        let moves = self.moves().into_iter().map(|(player, action)| replay::poker_move(player, action)).collect();
+        let mut states = replay::replay_scenario(&scenario, &TableEntropy::new(0), &moves)?;
+
+        // A hand where nobody could act runs out when the next turn begins
+        let players: Vec<u16> = (1..seats as u16 + 1).collect();
+        let mut state = UserState { ctx: Context::new(&players, None), g: states.pop().expect("A replay starts with the initial state") };
+        begin_turn(&mut state)?;
+        let replayed = state.g.last_hand.clone()
+            .ok_or(Box::new(Errors::InvalidHandHistory(String::from("the moves do not finish the hand"))))?;
+
+        let awards = |events: &Vec<HandEvent>| -> Vec<HandEvent> {
+            events.iter().filter(|event| match **event {
+                HandEvent::UncalledBetReturned { .. } | HandEvent::PotAwarded { .. } => true,
+                _ => false
+            }).cloned().collect()
+        };
+
+        if awards(&self.events) != awards(&replayed.events) {
+            return Err(Box::new(Errors::ResultMismatch(events::describe_result(&self.events), events::describe_result(&replayed.events))));
+        }
+
+        Ok(())
+    }
+
+    // Name of the best hand the cards make with the board
+    fn hand_label(&self, cards: &Vec<Card>) -> String {
+        let mut working_hand = cards.clone();
+        working_hand.extend_from_slice(&self.board);
+        if working_hand.len() < 7 {
+            return String::from("");
+        }
+        resolver::hand_to_string(&resolver::evaluate_best_hand(0, &working_hand).hand)
+    }
+}
+
+fn name(player: u16) -> String {
+    format!("Player {}", player)
+}
+
+fn all_in_suffix(all_in: bool) -> &'static str {
+    match all_in {
+        true => " and is all-in",
+        false => ""
+    }
+}
+
+// What each player collected from each pot as (pot, player, amount)
+fn collected(events: &Vec<HandEvent>) -> Vec<(usize, u16, Chips)> {
+    events.iter().filter_map(|event| match *event {
+        HandEvent::PotAwarded { pot, player, amount, .. } => Some((pot, player, amount)),
+        _ => None
+    }).collect()
+}
+
+fn blind_players(events: &Vec<HandEvent>) -> (Option<u16>, Option<u16>) {
+    let posted = |wanted: Blind| events.iter().filter_map(|event| match *event {
+        HandEvent::BlindPosted { player, blind, .. } if blind == wanted => Some(player),
+        _ => None
+    }).next();
+
+    (posted(Blind::Small), posted(Blind::Big))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CardRanking;
+
+    fn card(name: &str) -> Card {
+        let rank = "23456789TJQKA".find(name.chars().nth(0).unwrap()).unwrap() as u8;
+        let suit = "dchs".find(name.chars().nth(1).unwrap()).unwrap() as u8;
+        Card { suit: suit, rank: rank }
+    }
+
+    fn cards(names: &str) -> Vec<Card> {
+        names.split(' ').map(card).collect()
+    }
+
+    fn ranking(player: usize, hole_cards: &str) -> CardRanking {
+        resolver::evaluate_best_hand(player, &cards(&format!("{} 2h 7h 9c Qh 3s", hole_cards)))
+    }
+
+    // Heads-up: player 1 has the button and raises, player 2 calls, bets the flop and gets raised all in
+    fn history() -> HandHistory {
+        HandHistory {
+            hand_number: 3,
+            config: TableConfig::default(),
+            dealer: 1,
+            stacks: vec![Chips(30), Chips(64)],
+            hole_cards: vec![cards("Ah Kh"), cards("Qs Qd")],
+            board: cards("2h 7h 9c Qh 3s"),
+            events: vec![
+                HandEvent::HandStarted { hand_number: 3, dealer: 1, stacks: vec![Chips(30), Chips(64)] },
+                HandEvent::BlindPosted { player: 1, blind: Blind::Small, amount: Chips(1) },
+                HandEvent::BlindPosted { player: 2, blind: Blind::Big, amount: Chips(2) },
+                HandEvent::Raised { player: 1, to: Chips(6), all_in: false },
+                HandEvent::Called { player: 2, to: Chips(6), all_in: false },
+                HandEvent::StreetDealt { street: Street::Flop, cards: cards("2h 7h 9c") },
+                HandEvent::Bet { player: 2, to: Chips(40), all_in: false },
+                HandEvent::Called { player: 1, to: Chips(24), all_in: true },
+                HandEvent::UncalledBetReturned { player: 2, amount: Chips(16) },
+                HandEvent::StreetDealt { street: Street::Turn, cards: cards("Qh") },
+                HandEvent::StreetDealt { street: Street::River, cards: cards("3s") },
+                HandEvent::Shown { player: 2, cards: cards("Qs Qd") },
+                HandEvent::Shown { player: 1, cards: cards("Ah Kh") },
+                HandEvent::PotAwarded { pot: 0, player: 1, amount: Chips(60), ranking: Some(ranking(0, "Ah Kh")) },
+                HandEvent::HandEnded
+            ],
+            commitment: None,
+            seed: None
+        }
+    }
+
+    #[test]
+    fn writes_pokerstars_text() {
+        let text = history().to_pokerstars("Oasis 1", "2019/01/31 20:15:00 ET");
+        let expected = vec![
+            "PokerStars Hand #3: Hold'em No Limit (1/2) - 2019/01/31 20:15:00 ET",
+            "Table 'Oasis 1' 2-max Seat #1 is the button",
+            "Seat 1: Player 1 (30 in chips)",
+            "Seat 2: Player 2 (64 in chips)",
+            "Player 1: posts small blind 1",
+            "Player 2: posts big blind 2",
+            "*** HOLE CARDS ***",
+            "Player 1: raises 4 to 6",
+            "Player 2: calls 4",
+            "*** FLOP *** [2h 7h 9c]",
+            "Player 2: bets 40",
+            "Player 1: calls 24 and is all-in",
+            "Uncalled bet (16) returned to Player 2",
+            "*** TURN *** [2h 7h 9c] [Qh]",
+            "*** RIVER *** [2h 7h 9c Qh] [3s]",
+            "*** SHOW DOWN ***",
+            "Player 2: shows [Qs Qd] (Triple)",
+            "Player 1: shows [Ah Kh] (Flush)",
+            "Player 1 collected 60 from pot",
+            "*** SUMMARY ***",
+            "Total pot 60 | Rake 0",
+            "Board [2h 7h 9c Qh 3s]",
+            "Seat 1: Player 1 (button) (small blind) showed [Ah Kh] and won (60) with Flush",
+            "Seat 2: Player 2 (big blind) showed [Qs Qd] and lost with Triple"
+        ];
+        assert_eq!(text, expected.join("\n"));
+    }
+
+    #[test]
+    fn lists_the_moves_to_replay() {
+        let moves = history().moves();
+        assert_eq!(moves, vec![
+            (1, PokerAction::Raise { to: Chips(6) }),
+            (2, PokerAction::Call),
+            (2, PokerAction::Bet { to: Chips(40) }),
+            (1, PokerAction::Call)
+        ]);
+    }
+
+    #[test]
+    fn pays_the_hand_out_again() {
+        let mut hand = history();
+        assert!(hand.check_result().is_ok());
+
+        // The pair of queens cannot have won the main pot
+        hand.events[13] = HandEvent::PotAwarded { pot: 0, player: 2, amount: Chips(60), ranking: Some(ranking(1, "Qs Qd")) };
+        assert_eq!(hand.check_result().unwrap_err().to_string(), Errors::ResultMismatch(
+            String::from("Player 2 wins with Triple [Qs Qd], Player 1 shows [Ah Kh]"),
+            String::from("Player 1 wins with Flush [Ah Kh], Player 2 shows [Qs Qd]")).to_string());
+
+        // The bet nobody called goes back to the player who made it, it is not a pot
+        let mut hand = history();
+        hand.events.remove(8);
+        hand.events.insert(13, HandEvent::PotAwarded { pot: 1, player: 2, amount: Chips(16), ranking: Some(ranking(1, "Qs Qd")) });
+        assert!(hand.check_result().is_err());
+    }
+
+    #[test]
+    fn plays_the_moves_through_the_engine() {
+        // The big blind cannot raise before the button has acted
+        let mut hand = history();
+        hand.events[3] = HandEvent::Raised { player: 2, to: Chips(6), all_in: false };
+        assert_eq!(hand.check_result().unwrap_err().to_string(),
+            Errors::ReplayFailed(1, Errors::NotYourTurn(2).to_string()).to_string());
+    }
+}
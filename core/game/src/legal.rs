@@ -0,0 +1,325 @@
+/**
+ * Module that lists the moves poker_move will accept from a player
+ */
+
+use serde_json;
+use serde_json::Value;
+use std::error::Error;
+use actions::{self, PokerAction, Preset};
+use betting::{self, RaiseContext};
+use chips::Chips;
+use pots;
+use {Errors, State, Street, raise_context};
+
+/// Everything a UI or bot needs to offer the player to act.
+/// Amounts are totals on the table this betting round, like the moves themselves.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LegalActions {
+    pub actions: Vec<PokerAction>,
+    pub to_call: Chips,
+    pub can_check: bool,
+    pub min_raise_to: Option<Chips>,
+    pub max_raise_to: Option<Chips>
+}
+
+impl LegalActions {
+    fn none() -> LegalActions {
+        LegalActions {
+            actions: Vec::new(),
+            to_call: Chips::zero(),
+            can_check: false,
+            min_raise_to: None,
+            max_raise_to: None
+        }
+    }
+}
+
+// Giving up the hand at the showdown is only allowed while someone else still contests every
+// pot the player could win, otherwise a player who did not cover that pot would be paid from it
+fn can_muck(state: &State, player_idx: usize) -> bool {
+    pots::build_pots(&state.hand_contributions, &state.still_in).iter()
+        .filter(|pot| pot.eligible.contains(&player_idx))
+        .all(|pot| pot.eligible.iter().any(|&other| other != player_idx))
+}
+
+// All in is a call when the stack does not cover the bet, otherwise a raise that has to fit
+// under the largest raise, which pot-limit and fixed-limit may cap below the stack
+fn can_go_all_in(ctx: &RaiseContext, limits: Option<(Chips, Chips)>) -> bool {
+    match limits {
+        Some((_, max_raise_to)) => ctx.all_in_to() <= max_raise_to,
+        None => ctx.all_in_to() <= ctx.bet_amount
+    }
+}
+
+/**
+ * The actions player_id may send right now, nothing if it is not their turn.
+ */
+pub fn legal_actions(state: &State, player_id: u16) -> LegalActions {
+
+    if player_id == 0 || player_id != state.to_act || player_id as usize > state.chips.len() {
+        return LegalActions::none();
+    }
+    let player_idx = player_id as usize - 1;
+
+    if state.street == Street::Showdown && state.needs_action[player_idx] {
+        let mut legal = LegalActions::none();
+        legal.actions = match can_muck(state, player_idx) {
+            true => vec![PokerAction::Show, PokerAction::Muck],
+            false => vec![PokerAction::Show]
+        };
+        return legal;
+    }
+
+    if !state.street.is_betting() || !state.still_in[player_idx] || !state.needs_action[player_idx] {
+        return LegalActions::none();
+    }
+
+    let ctx = raise_context(state, player_idx);
+    let to_call = ctx.to_call().min(ctx.stack);
+    let can_check = to_call.is_zero();
+
+    let mut actions = vec![PokerAction::Fold];
+    actions.push(if can_check { PokerAction::Check } else { PokerAction::Call });
+
+    let limits = match state.can_raise[player_idx] {
+        true => betting::raise_limits(&ctx, &state.config.betting_structure),
+        false => None
+    };
+
+    if let Some((min_raise_to, _)) = limits {
+
+        let to = min_raise_to;
+        actions.push(if state.bet_amount.is_zero() { PokerAction::Bet { to: to } } else { PokerAction::Raise { to: to } });
+
+        for preset in &[Preset::Min, Preset::HalfPot, Preset::Pot] {
+            actions.push(PokerAction::BetPreset { preset: *preset });
+        }
+    }
+
+    if can_go_all_in(&ctx, limits) {
+        actions.push(PokerAction::AllIn);
+    }
+
+    if state.can_straddle {
+        actions.push(PokerAction::Straddle);
+    }
+
+    LegalActions {
+        actions: actions,
+        to_call: to_call,
+        can_check: can_check,
+        min_raise_to: limits.map(|(min_raise_to, _)| min_raise_to),
+        max_raise_to: limits.map(|(_, max_raise_to)| max_raise_to)
+    }
+}
+
+/**
+ * Check that player_id may send action right now, without changing anything.
+ * poker_move runs this before applying a move, so a rejected move leaves the state as it was.
+ */
+pub fn validate_action(state: &State, player_id: u16, action: &PokerAction) -> Result<(), Box<Error>> {
+
+    if player_id == 0 || player_id != state.to_act || player_id as usize > state.chips.len() {
+        return Err(Box::new(Errors::NotYourTurn(player_id)));
+    }
+    let player_idx = player_id as usize - 1;
+
+    // At the showdown the only choice is to show or muck
+    match *action {
+        PokerAction::Show | PokerAction::Muck => {
+            if state.street != Street::Showdown {
+                return Err(Box::new(Errors::NotAtShowdown));
+            }
+            if !state.needs_action[player_idx] {
+                return Err(Box::new(Errors::NotYourTurn(player_id)));
+            }
+            if *action == PokerAction::Muck && !can_muck(state, player_idx) {
+                return Err(Box::new(Errors::MustShow(player_id)));
+            }
+            return Ok(());
+        },
+        _ => ()
+    }
+
+    if !state.street.is_betting() {
+        return Err(Box::new(Errors::BettingClosed));
+    }
+    if !state.still_in[player_idx] {
+        return Err(Box::new(Errors::AlreadyFolded(player_id)));
+    }
+    if !state.needs_action[player_idx] {
+        return Err(Box::new(Errors::NotYourTurn(player_id)));
+    }
+
+    let ctx = raise_context(state, player_idx);
+    let limits = match state.can_raise[player_idx] {
+        true => betting::raise_limits(&ctx, &state.config.betting_structure),
+        false => None
+    };
+
+    match *action {
+
+        PokerAction::Fold | PokerAction::Call => Ok(()),
+
+        PokerAction::Check => match ctx.to_call() {
+            to_call if to_call.is_zero() => Ok(()),
+            to_call => Err(Box::new(Errors::CheckFacingBet(to_call)))
+        },
+
+        PokerAction::Bet { to } | PokerAction::Raise { to } => {
+
+            if to > ctx.all_in_to() {
+                let needed = to.saturating_sub(ctx.on_table);
+                return Err(Box::new(Errors::InsufficientChips(needed, ctx.stack)));
+            }
+
+            let (min_raise_to, max_raise_to) = limits.ok_or(Box::new(Errors::RaiseNotAllowed))?;
+            if to < min_raise_to {
+                return Err(Box::new(Errors::RaiseBelowMinimum(to, min_raise_to)));
+            }
+            if to > max_raise_to {
+                return Err(Box::new(Errors::RaiseAboveMaximum(to, max_raise_to)));
+            }
+            Ok(())
+        },
+
+        PokerAction::BetPreset { .. } => match limits {
+            Some(_) => Ok(()),
+            None => Err(Box::new(Errors::RaiseNotAllowed))
+        },
+
+        PokerAction::AllIn => match can_go_all_in(&ctx, limits) {
+            true => Ok(()),
+            false => Err(Box::new(Errors::RaiseNotAllowed))
+        },
+
+        PokerAction::Straddle => match state.can_straddle {
+            true => Ok(()),
+            false => Err(Box::new(Errors::RaiseNotAllowed))
+        },
+
+        PokerAction::Show | PokerAction::Muck => unreachable!()
+    }
+}
+
+/**
+ * Decode and validate the arguments of a poker_move, for callers that want to
+ * reject a move before handing it to the game server.
+ */
+pub fn check_move(state: &State, player_id: u16, args: &Value) -> Result<PokerAction, Box<Error>> {
+    let action = actions::decode_move(args)?;
+    validate_action(state, player_id, &action)?;
+    Ok(action)
+}
+
+/**
+ * Read a State from JSON. Accepts the state itself, or a store state
+ * that holds it under "g" or "G" next to the framework context.
+ */
+pub fn state_from_value(value: &Value) -> Option<State> {
+
+    let inner = value.get("g").or_else(|| value.get("G")).unwrap_or(value);
+
+    serde_json::from_value(inner.clone()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use betting::BettingStructure;
+    use config::TableConfig;
+
+    // Heads-up, blinds 1/2 posted, the dealer is first to act
+    fn preflop_state() -> State {
+        let mut state = State::default();
+        state.config = TableConfig::default();
+        state.chips = vec![Chips(63), Chips(62)];
+        state.chip_table = vec![Chips(1), Chips(2)];
+        state.hand_contributions = vec![Chips(1), Chips(2)];
+        state.needs_action = vec![true, true];
+        state.can_raise = vec![true, true];
+        state.still_in = vec![true, true];
+        state.hands = vec![Vec::new(), Vec::new()];
+        state.bet_amount = Chips(2);
+        state.last_raise = Chips(2);
+        state.chips_in_play = Chips(128);
+        state.bets_this_street = 1;
+        state.street = Street::Preflop;
+        state.to_act = 1;
+        state
+    }
+
+    #[test]
+    fn facing_the_big_blind() {
+        let legal = legal_actions(&preflop_state(), 1);
+        assert_eq!(legal.to_call, Chips(1));
+        assert!(!legal.can_check);
+        assert_eq!(legal.min_raise_to, Some(Chips(4)));
+        assert_eq!(legal.max_raise_to, Some(Chips(64)));
+        assert!(legal.actions.contains(&PokerAction::Call));
+        assert!(legal.actions.contains(&PokerAction::Raise { to: Chips(4) }));
+        assert!(!legal.actions.contains(&PokerAction::Check));
+    }
+
+    #[test]
+    fn pot_limit_only_goes_all_in_under_the_pot() {
+        let mut state = preflop_state();
+        state.config.betting_structure = BettingStructure::PotLimit;
+        let legal = legal_actions(&state, 1);
+        assert_eq!(legal.max_raise_to, Some(Chips(6)));
+        assert!(!legal.actions.contains(&PokerAction::AllIn));
+        assert_eq!(validate_action(&state, 1, &PokerAction::AllIn).unwrap_err().to_string(), Errors::RaiseNotAllowed.to_string());
+
+        state.chips[0] = Chips(4);
+        assert!(legal_actions(&state, 1).actions.contains(&PokerAction::AllIn));
+        assert!(validate_action(&state, 1, &PokerAction::AllIn).is_ok());
+    }
+
+    #[test]
+    fn nothing_when_it_is_not_your_turn() {
+        assert_eq!(legal_actions(&preflop_state(), 2), LegalActions::none());
+    }
+
+    #[test]
+    fn rejects_moves_with_a_reason() {
+        let state = preflop_state();
+        let rejected = |player_id, action| validate_action(&state, player_id, &action).unwrap_err().to_string();
+
+        assert_eq!(rejected(2, PokerAction::Call), Errors::NotYourTurn(2).to_string());
+        assert_eq!(rejected(1, PokerAction::Check), Errors::CheckFacingBet(Chips(1)).to_string());
+        assert_eq!(rejected(1, PokerAction::Raise { to: Chips(3) }), Errors::RaiseBelowMinimum(Chips(3), Chips(4)).to_string());
+        assert_eq!(rejected(1, PokerAction::Raise { to: Chips(70) }), Errors::InsufficientChips(Chips(69), Chips(63)).to_string());
+        assert!(validate_action(&state, 1, &PokerAction::Raise { to: Chips(64) }).is_ok());
+    }
+
+    #[test]
+    fn folded_and_finished_hands() {
+        let mut state = preflop_state();
+        state.still_in[0] = false;
+        assert_eq!(validate_action(&state, 1, &PokerAction::Call).unwrap_err().to_string(), Errors::AlreadyFolded(1).to_string());
+
+        state.street = Street::HandComplete;
+        assert_eq!(validate_action(&state, 1, &PokerAction::Fold).unwrap_err().to_string(), Errors::BettingClosed.to_string());
+    }
+
+    #[test]
+    fn show_or_muck_at_the_showdown() {
+        let mut state = preflop_state();
+        assert_eq!(validate_action(&state, 1, &PokerAction::Show).unwrap_err().to_string(), Errors::NotAtShowdown.to_string());
+
+        state.street = Street::Showdown;
+        state.needs_action = vec![false, true];
+        state.shown = vec![true, false];
+        state.hand_contributions = vec![Chips(2), Chips(2)];
+        state.to_act = 2;
+        assert_eq!(legal_actions(&state, 2).actions, vec![PokerAction::Show, PokerAction::Muck]);
+        assert_eq!(validate_action(&state, 2, &PokerAction::Call).unwrap_err().to_string(), Errors::BettingClosed.to_string());
+    }
+
+    #[test]
+    fn reads_wrapped_states() {
+        let state = preflop_state();
+        let wrapped = json!({ "g": serde_json::to_value(&state).unwrap(), "ctx": {} });
+        assert_eq!(state_from_value(&wrapped).unwrap().to_act, 1);
+    }
+}
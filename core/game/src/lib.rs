@@ -1,4 +1,19 @@
 mod resolver;
+mod pots;
+mod betting;
+mod chips;
+mod deck;
+mod street;
+pub mod actions;
+pub mod legal;
+pub mod config;
+pub mod events;
+pub mod history;
+pub mod ohh;
+pub mod replay;
+pub mod scenario;
+pub mod entropy;
+pub mod commitment;
 
 #[macro_use]
 extern crate serde_derive;
@@ -8,21 +23,27 @@ extern crate serde_json;
 extern crate quick_error;
 
 extern crate rand;
+extern crate tiny_keccak;
 
 extern crate oasis_game_core;
 extern crate oasis_game_core_derive;
 
 use serde_json::Value;
+use std::cmp::Ordering;
 use std::error::Error;
 use oasis_game_core::*;
 use oasis_game_core_derive::{flow, moves};
-use rand::{Rng, SeedableRng, ChaChaRng};
-
-const NUM_DECK_SUITS: usize = 4;
-const NUM_DECK_VALUES: usize = 13;
-
-const NUM_PLAYERS: usize = 2;
-const STANDARD_BET_SIZE: u16 = 2;
+use pots::Pot;
+use betting::{BetSize, BettingStructure, RaiseContext};
+use config::{ButtonRule, HandTransition, TableConfig, MIN_PLAYERS, MAX_PLAYERS};
+use entropy::TableEntropy;
+use actions::{PokerAction, Preset};
+use events::{Blind, HandEvent};
+use history::HandHistory;
+
+pub use chips::Chips;
+pub use deck::Deck;
+pub use street::Street;
 
 /// Error types.
 quick_error! {
@@ -32,96 +53,527 @@ quick_error! {
             description("invalid move")
             display("This move cannot be made.")
         }
+        InvalidTableSize(num_players: u16) {
+            description("invalid table size")
+            display("A table must seat between {} and {} players, found {}.", MIN_PLAYERS, MAX_PLAYERS, num_players)
+        }
+        UnsupportedVersion(version: u8) {
+            description("unsupported move protocol version")
+            display("Move protocol version {} is not supported.", version)
+        }
+        InvalidTableConfig(reason: &'static str) {
+            description("invalid table config")
+            display("This table cannot be created: {}.", reason)
+        }
+        NotYourTurn(player_id: u16) {
+            description("not your turn")
+            display("It is not player {}'s turn to act.", player_id)
+        }
+        AlreadyFolded(player_id: u16) {
+            description("player already folded")
+            display("Player {} has already folded this hand.", player_id)
+        }
+        InsufficientChips(needed: Chips, available: Chips) {
+            description("insufficient chips")
+            display("This move needs {} chips, only {} are left.", needed, available)
+        }
+        CheckFacingBet(to_call: Chips) {
+            description("cannot check facing a bet")
+            display("Cannot check, {} to call.", to_call)
+        }
+        RaiseBelowMinimum(raise_to: Chips, min_raise_to: Chips) {
+            description("raise below the minimum")
+            display("Cannot raise to {}, the minimum is {}.", raise_to, min_raise_to)
+        }
+        RaiseAboveMaximum(raise_to: Chips, max_raise_to: Chips) {
+            description("raise above the maximum")
+            display("Cannot raise to {}, the maximum is {}.", raise_to, max_raise_to)
+        }
+        RaiseNotAllowed {
+            description("raise not allowed")
+            display("The betting is capped or has not been reopened.")
+        }
+        BettingClosed {
+            description("betting closed")
+            display("There is no betting round in progress.")
+        }
+        NotAtShowdown {
+            description("not at showdown")
+            display("Cards are only shown or mucked at the showdown.")
+        }
+        NoEntropy {
+            description("no entropy")
+            display("The table has no entropy to shuffle the deck with.")
+        }
+        MustShow(player: u16) {
+            description("must show")
+            display("Player {} is the last one contesting a pot and has to show.", player)
+        }
+        ChipOverflow {
+            description("chip overflow")
+            display("This amount of chips is too large.")
+        }
+        ChipsNotConserved(expected: Chips, found: Chips) {
+            description("chips not conserved")
+            display("The table should hold {} chips, found {}.", expected, found)
+        }
+        DeckExhausted {
+            description("deck exhausted")
+            display("There are no cards left to deal.")
+        }
+        ReplayFailed(move_number: usize, reason: String) {
+            description("replay failed")
+            display("Move {} of the replay failed: {}", move_number, reason)
+        }
+        ReplayMismatch(move_number: usize) {
+            description("replay mismatch")
+            display("The replayed game differs from the supplied state after move {}.", move_number)
+        }
+        InvalidScenario(reason: String) {
+            description("invalid scenario")
+            display("This position cannot be set up: {}.", reason)
+        }
+        InvalidHandHistory(reason: String) {
+            description("invalid hand history")
+            display("This hand history cannot be read: {}.", reason)
+        }
+        ResultMismatch(recorded: String, replayed: String) {
+            description("result mismatch")
+            display("The hand was recorded as \"{}\" but plays out as \"{}\".", recorded, replayed)
+        }
+        DealMismatch(reason: String) {
+            description("deal mismatch")
+            display("The hand was not dealt from the committed deck: {}.", reason)
+        }
     }
 }
 
 /// Define the state shape.
-/// State type: false if not in deck, true if still in deck.
-pub type CardDeck = [[bool; NUM_DECK_VALUES]; NUM_DECK_SUITS];
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Card {
     pub suit: u8,
     pub rank: u8
 }
 
-#[derive(Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CardRanking {
     pub player: usize,
     pub hand: u8,
     pub tiebreak: Vec<u8>
 }
 
+// Rankings are ordered by hand strength only, two players can hold equal hands
+impl Ord for CardRanking {
+    fn cmp(&self, other: &CardRanking) -> Ordering {
+        self.hand.cmp(&other.hand)
+            .then_with(|| self.tiebreak.cmp(&other.tiebreak))
+    }
+}
+
+impl PartialOrd for CardRanking {
+    fn partial_cmp(&self, other: &CardRanking) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for CardRanking {
+    fn eq(&self, other: &CardRanking) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for CardRanking {}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct State {
-    pub cards: Option<CardDeck>,
+    pub entropy: Option<TableEntropy>,
+    pub deck: Option<Deck>,
+    pub deck_commitment: Option<String>,
+    pub deck_seed: Option<String>,
     pub hands: Vec<Vec<Card>>,
     pub needs_action: Vec<bool>,
+    pub can_raise: Vec<bool>,
     pub still_in: Vec<bool>,
     pub dealer: u16,
     pub card_table: Vec<Card>,
-    pub chips: Vec<u16>,
-    pub chip_table: Vec<u16>,
-    pub bet_amount: u16,
-    pub hand_pot: u16,
-    pub hand_over: bool,
+    pub chips: Vec<Chips>,
+    pub chip_table: Vec<Chips>,
+    pub hand_contributions: Vec<Chips>,
+    pub bet_amount: Chips,
+    pub last_raise: Chips,
+    pub bets_this_street: u8,
+    pub hand_pot: Chips,
+    pub chips_in_play: Chips,
+    pub config: TableConfig,
+    pub can_straddle: bool,
+    pub street: Street,
+    pub hand_number: u32,
+    pub small_blind_seat: u16,
+    pub big_blind_seat: u16,
+    pub missed_big_blind: Vec<bool>,
+    pub last_aggressor: u16,
+    pub shown: Vec<bool>,
+    pub to_act: u16,
+    pub events: Vec<HandEvent>,
+    pub last_hand: Option<HandHistory>,
     pub last_move: String,
-    pub hand_result: String
+    pub hand_result: String,
+    pub last_hand_result: String,
+    pub last_board: Vec<Card>,
+    pub last_shown_hands: Vec<Vec<Card>>,
+    pub result_moves_left: u16
 }
 
 impl Default for State {
 
-    // All cards are hidden in deck
+    // The deck comes from the table's deck source when a hand is dealt, seats are filled when the first hand is dealt.
+    // No hand has been played, so the table starts out waiting for the first deal.
     fn default() -> Self {
         
         State {
-            cards: Some([[true; NUM_DECK_VALUES]; NUM_DECK_SUITS]),
-            hands: vec![Vec::new(), Vec::new()],
-            needs_action: vec![true; NUM_PLAYERS],
-            still_in: vec![true; NUM_PLAYERS],
+            entropy: None,
+            deck: None,
+            deck_commitment: None,
+            deck_seed: None,
+            hands: Vec::new(),
+            needs_action: Vec::new(),
+            can_raise: Vec::new(),
+            still_in: Vec::new(),
             dealer: 1,
             card_table: Vec::new(),
-            chips: vec![64; NUM_PLAYERS],
-            chip_table: vec![0; NUM_PLAYERS],
-            bet_amount: 0,
-            hand_pot: 0,
-            hand_over: true,
+            chips: Vec::new(),
+            chip_table: Vec::new(),
+            hand_contributions: Vec::new(),
+            bet_amount: Chips::zero(),
+            last_raise: Chips::zero(),
+            bets_this_street: 0,
+            hand_pot: Chips::zero(),
+            chips_in_play: Chips::zero(),
+            config: TableConfig::default(),
+            can_straddle: false,
+            street: Street::HandComplete,
+            hand_number: 0,
+            small_blind_seat: 0,
+            big_blind_seat: 0,
+            missed_big_blind: Vec::new(),
+            last_aggressor: 0,
+            shown: Vec::new(),
+            to_act: 0,
+            events: Vec::new(),
+            last_hand: None,
             last_move: String::from("New Hand"),
-            hand_result: String::from("")
+            hand_result: String::from(""),
+            last_hand_result: String::from(""),
+            last_board: Vec::new(),
+            last_shown_hands: Vec::new(),
+            result_moves_left: 0
         }
     }
 }
 
-// Returns a ([0-3],[0-12]) idx of the card drawn
-fn draw_cards(deck: &mut CardDeck, seed: u128, num_cards_needed: u8) -> Vec<Card> {
-    
-    let mut seed_arr = [0 as u8; 32];
-    for (i, byte) in seed.to_le_bytes().iter().enumerate() {
-        seed_arr[i] = *byte
+fn num_players(state: &UserState<State>) -> usize {
+    state.ctx.num_players as usize
+}
+
+// A player can act if they are in the hand and have chips behind
+fn can_act(state: &UserState<State>, player_idx: usize) -> bool {
+    state.g.still_in[player_idx] && !state.g.chips[player_idx].is_zero()
+}
+
+// Size every per-seat vector to the number of seats at the table
+fn seat_table(state: &mut State) {
+
+    let seats = state.config.num_seats as usize;
+    state.hands = vec![Vec::new(); seats];
+    state.needs_action = vec![false; seats];
+    state.can_raise = vec![true; seats];
+    state.still_in = vec![true; seats];
+    state.chips = vec![state.config.starting_chips; seats];
+    state.chip_table = vec![Chips::zero(); seats];
+    state.hand_contributions = vec![Chips::zero(); seats];
+    state.missed_big_blind = vec![false; seats];
+    state.shown = vec![false; seats];
+    state.chips_in_play = state.config.starting_chips.saturating_mul(seats as u64);
+}
+
+/**
+ * Check the players in the game fill the table, seating them if needed.
+ * Only seats players once, before the first hand is dealt.
+ */
+fn seat_players(state: &mut UserState<State>) -> Result<(), Box<Error>> {
+
+    let num_players = state.ctx.num_players;
+
+    if num_players < MIN_PLAYERS || num_players > MAX_PLAYERS || num_players != state.g.config.num_seats {
+        return Err(Box::new(Errors::InvalidTableSize(num_players)));
+    }
+
+    if state.g.chips.len() != num_players as usize {
+        seat_table(&mut state.g);
+    }
+
+    Ok(())
+}
+
+// Move chips from a player's stack to the table, counting them towards the pots they can win
+fn commit_chips(state: &mut UserState<State>, player_idx: usize, amount: Chips) -> Result<(), Box<Error>> {
+
+    let stack = state.g.chips[player_idx];
+    let remaining = stack.checked_sub(amount)
+        .ok_or(Box::new(Errors::InsufficientChips(amount, stack)))?;
+    let on_table = state.g.chip_table[player_idx].checked_add(amount)
+        .ok_or(Box::new(Errors::ChipOverflow))?;
+    let contribution = state.g.hand_contributions[player_idx].checked_add(amount)
+        .ok_or(Box::new(Errors::ChipOverflow))?;
+
+    state.g.chips[player_idx] = remaining;
+    state.g.chip_table[player_idx] = on_table;
+    state.g.hand_contributions[player_idx] = contribution;
+    Ok(())
+}
+
+/**
+ * Check that no chips were created or lost: the stacks, the bets on the table
+ * and the pot must add up to the chips the table was seated with.
+ */
+fn check_chips_conserved(state: &State) -> Result<(), Box<Error>> {
+
+    let counted = state.chips.iter()
+        .chain(state.chip_table.iter())
+        .chain(Some(&state.hand_pot));
+    let total = Chips::checked_sum(counted).ok_or(Box::new(Errors::ChipOverflow))?;
+
+    if total != state.chips_in_play {
+        return Err(Box::new(Errors::ChipsNotConserved(state.chips_in_play, total)));
+    }
+
+    Ok(())
+}
+
+// Next seat after player_idx that has chips to play the hand with
+fn next_seat_with_chips(state: &UserState<State>, player_idx: usize) -> usize {
+
+    let mut seat = (player_idx + 1) % num_players(state);
+
+    while state.g.chips[seat].is_zero() && seat != player_idx {
+        seat = (seat + 1) % num_players(state);
+    }
+
+    seat
+}
+
+fn count_seats_with_chips(state: &UserState<State>) -> usize {
+    state.g.chips.iter().filter(|chips| !chips.is_zero()).count()
+}
+
+/**
+ * Returns the (small blind, big blind) seats relative to the dealer.
+ * Heads-up, the dealer posts the small blind.
+ */
+fn blind_seats(state: &UserState<State>) -> (usize, usize) {
+
+    let dealer_idx = state.g.dealer as usize - 1;
+
+    let small_blind = match count_seats_with_chips(state) {
+        2 => dealer_idx,
+        _ => next_seat_with_chips(state, dealer_idx)
     };
 
-    let mut rng = ChaChaRng::from_seed(seed_arr);
+    (small_blind, next_seat_with_chips(state, small_blind))
+}
 
-    let mut num_cards_drawn: u8 = 0;
-    let mut card_vec = Vec::new();
+// Post a blind, a short stack posts what they have. Blinds play as a bet.
+fn post_blind(state: &mut UserState<State>, player_idx: usize, blind: Blind, amount: Chips) -> Result<(), Box<Error>> {
 
-    while num_cards_drawn != num_cards_needed {
-        
-        let card_suit = rng.gen_range(0, NUM_DECK_SUITS);
-        let card_rank = rng.gen_range(0, NUM_DECK_VALUES);
-    
-        if deck[card_suit][card_rank] {
-            num_cards_drawn += 1;
-            deck[card_suit][card_rank] = false;
-            card_vec.push(Card {
-                suit: card_suit as u8, 
-                rank: card_rank as u8
-            });
+    let posted = amount.min(state.g.chips[player_idx]);
+    commit_chips(state, player_idx, posted)?;
+    log_event(state, HandEvent::BlindPosted { player: player_idx as u16 + 1, blind: blind, amount: posted });
+
+    if state.g.chip_table[player_idx] > state.g.bet_amount {
+        state.g.bet_amount = state.g.chip_table[player_idx];
+    }
+    if state.g.chips[player_idx].is_zero() {
+        state.g.needs_action[player_idx] = false;
+    }
+    Ok(())
+}
+
+// Post an ante, which goes straight into the pot and does not count towards calling
+fn post_ante(state: &mut UserState<State>, player_idx: usize, amount: Chips) -> Result<(), Box<Error>> {
+
+    let posted = amount.min(state.g.chips[player_idx]);
+    let contribution = state.g.hand_contributions[player_idx].checked_add(posted)
+        .ok_or(Box::new(Errors::ChipOverflow))?;
+    let hand_pot = state.g.hand_pot.checked_add(posted)
+        .ok_or(Box::new(Errors::ChipOverflow))?;
+
+    state.g.chips[player_idx] = state.g.chips[player_idx].saturating_sub(posted);
+    state.g.hand_contributions[player_idx] = contribution;
+    state.g.hand_pot = hand_pot;
+    log_event(state, HandEvent::AntePosted { player: player_idx as u16 + 1, amount: posted });
+
+    if state.g.chips[player_idx].is_zero() {
+        state.g.needs_action[player_idx] = false;
+    }
+    Ok(())
+}
+
+/**
+ * Place the button and the blinds for the next hand. The first hand keeps the dealer it was
+ * created with. After that a moving button goes to the next player with chips, while a dead
+ * button moves the big blind on by one player and lets the small blind and the button follow
+ * it onto the seats the blinds were last posted from, even when those players have busted.
+ * Heads-up, the big blind moves on and the other player has the button and the small blind.
+ */
+fn place_button(state: &mut UserState<State>) {
+
+    if state.g.hand_number == 0 || state.g.big_blind_seat == 0 {
+        let (small_blind, big_blind) = blind_seats(state);
+        state.g.small_blind_seat = small_blind as u16 + 1;
+        state.g.big_blind_seat = big_blind as u16 + 1;
+        return;
+    }
+
+    let last_big_blind = state.g.big_blind_seat as usize - 1;
+    let (dealer, small_blind, big_blind) = match (state.g.config.button_rule, count_seats_with_chips(state)) {
+
+        (_, 2) => {
+            let big_blind = next_seat_with_chips(state, last_big_blind);
+            let dealer = next_seat_with_chips(state, big_blind);
+            (dealer, dealer, big_blind)
+        },
+
+        (ButtonRule::Dead, _) => {
+            let big_blind = next_seat_with_chips(state, last_big_blind);
+            (state.g.small_blind_seat as usize - 1, last_big_blind, big_blind)
+        },
+
+        (ButtonRule::Moving, _) => {
+            state.g.dealer = next_seat_with_chips(state, state.g.dealer as usize - 1) as u16 + 1;
+            let (small_blind, big_blind) = blind_seats(state);
+            (state.g.dealer as usize - 1, small_blind, big_blind)
+        }
+    };
+
+    // Busted seats the big blind passed over owe it if they are ever dealt in again
+    let mut seat = (last_big_blind + 1) % num_players(state);
+    while seat != big_blind && seat != last_big_blind {
+        if state.g.chips[seat].is_zero() {
+            state.g.missed_big_blind[seat] = true;
         }
+        seat = (seat + 1) % num_players(state);
     }
 
-    return card_vec;
-} 
+    state.g.dealer = dealer as u16 + 1;
+    state.g.small_blind_seat = small_blind as u16 + 1;
+    state.g.big_blind_seat = big_blind as u16 + 1;
+}
+
+/**
+ * Forced bet phase, run once all players are dealt in.
+ * A dead small blind is not posted. A player who missed the big blind posts it
+ * before playing their next hand, unless they are in the blinds.
+ */
+fn post_forced_bets(state: &mut UserState<State>) -> Result<(), Box<Error>> {
+
+    let forced_bets = state.g.config.forced_bets.clone();
+    let small_blind = state.g.small_blind_seat as usize - 1;
+    let big_blind = state.g.big_blind_seat as usize - 1;
+
+    if state.g.still_in[small_blind] {
+        post_blind(state, small_blind, Blind::Small, forced_bets.small_blind)?;
+    }
+    post_blind(state, big_blind, Blind::Big, forced_bets.big_blind)?;
+    state.g.bets_this_street = 1;
+
+    for i in 0..num_players(state) {
+        if state.g.missed_big_blind[i] && state.g.still_in[i] {
+            if i != small_blind && i != big_blind {
+                post_blind(state, i, Blind::Missed, forced_bets.big_blind)?;
+            }
+            state.g.missed_big_blind[i] = false;
+        }
+    }
+
+    if !forced_bets.ante.is_zero() {
+        if forced_bets.big_blind_ante {
+            post_ante(state, big_blind, forced_bets.ante)?;
+        } else {
+            for i in 0..num_players(state) {
+                if state.g.still_in[i] {
+                    post_ante(state, i, forced_bets.ante)?;
+                }
+            }
+        }
+    }
+
+    // Straddling is only open to the first player to act, before anyone else acts
+    state.g.can_straddle = forced_bets.straddle && count_seats_with_chips(state) > 2;
+    Ok(())
+}
+
+fn raise_context(state: &State, player_idx: usize) -> RaiseContext {
+    RaiseContext {
+        stack: state.chips[player_idx],
+        on_table: state.chip_table[player_idx],
+        bet_amount: state.bet_amount,
+        last_raise: state.last_raise,
+        pot: state.hand_pot.saturating_add(state.chip_table.iter().sum()),
+        bets_this_street: state.bets_this_street
+    }
+}
+
+// Size of an opening bet on the current street, the big blind unless playing fixed-limit
+fn street_bet_unit(state: &State) -> Chips {
+    match state.config.betting_structure {
+        BettingStructure::FixedLimit { small_bet, big_bet, .. } => match state.street {
+            Street::Turn | Street::River => big_bet,
+            _ => small_bet
+        },
+        _ => state.config.bet_unit
+    }
+}
+
+/**
+ * Raise the bet to raise_to, the total the player has on the table this round.
+ * A full raise reopens the action for everyone. An all-in that is smaller than
+ * the last raise only lets players who already acted call or fold.
+ */
+fn raise_bet(state: &mut UserState<State>, player_idx: usize, raise_to: Chips) -> Result<(), Box<Error>> {
+
+    let needed_bet = raise_to.saturating_sub(state.g.chip_table[player_idx]);
+    commit_chips(state, player_idx, needed_bet)?;
+
+    let raise_size = raise_to.saturating_sub(state.g.bet_amount);
+    let full_raise = raise_size >= state.g.last_raise;
+
+    if raise_to > state.g.bet_amount {
+        state.g.last_aggressor = player_idx as u16 + 1;
+    }
+
+    if full_raise {
+        state.g.last_raise = raise_size;
+        state.g.bets_this_street = state.g.bets_this_street.saturating_add(1);
+    }
+    state.g.bet_amount = raise_to;
+
+    // Since the bet has been raised, everyone who is still in the hand needs action.
+    for i in 0..num_players(state) {
+        if i != player_idx && can_act(state, i) {
+
+            if full_raise {
+                state.g.can_raise[i] = true;
+            } else if !state.g.needs_action[i] {
+                state.g.can_raise[i] = false;
+            }
+
+            state.g.needs_action[i] = true;
+        }
+    }
+
+    state.g.needs_action[player_idx] = false;
+    Ok(())
+}
 
 // The hand is over because everyone but one person folded  
 fn hand_is_over_folded(state: &UserState<State>) -> (bool, usize) {
@@ -129,7 +581,7 @@ fn hand_is_over_folded(state: &UserState<State>) -> (bool, usize) {
     let mut players_in = 0;
     let mut winner_idx = 0;
 
-    for i in 0..NUM_PLAYERS {
+    for i in 0..num_players(state) {
         
         if state.g.still_in[i] {
             players_in += 1;
@@ -145,7 +597,7 @@ fn hand_is_over_folded(state: &UserState<State>) -> (bool, usize) {
 // The round is over because all bets are equal 
 fn betting_round_is_over(state: &UserState<State>) -> bool {
 
-    for i in 0..NUM_PLAYERS {
+    for i in 0..num_players(state) {
         
         if state.g.still_in[i] && state.g.needs_action[i] {
             return false;
@@ -155,447 +607,752 @@ fn betting_round_is_over(state: &UserState<State>) -> bool {
     true    
 }
 
+// No more betting is possible because at most one player in the hand has chips behind
 fn is_all_in_hand(state: &UserState<State>) -> bool {
 
-    for i in 0..NUM_PLAYERS {
+    let mut players_with_chips = 0;
+
+    for i in 0..num_players(state) {
         
-        if state.g.still_in[i] && state.g.chips[i] == 0 {
-            return true;
+        if can_act(state, i) {
+            players_with_chips += 1;
         } 
     }
 
-    false
+    players_with_chips <= 1
+
+}
 
+// Betting is closed when nobody can be raised and nobody has anything left to call
+fn betting_is_closed(state: &UserState<State>) -> bool {
+    is_all_in_hand(state) && (0..num_players(state))
+        .all(|i| !can_act(state, i) || state.g.chip_table[i] >= state.g.bet_amount)
 }
 
 /**
  * Start the next betting round, ignores all in hands.
  */
-fn next_betting_round(state: &mut UserState<State>) {
+fn next_betting_round(state: &mut UserState<State>) -> Result<(), Box<Error>> {
     
     println!("Start a new betting round.");
 
-    for i in 0..NUM_PLAYERS {
+    for i in 0..num_players(state) {
         
-        state.g.hand_pot += state.g.chip_table[i];
-        state.g.chip_table[i] = 0;
+        state.g.hand_pot = state.g.hand_pot.checked_add(state.g.chip_table[i])
+            .ok_or(Box::new(Errors::ChipOverflow))?;
+        state.g.chip_table[i] = Chips::zero();
 
-        if state.g.still_in[i] {
+        if can_act(state, i) {
             state.g.needs_action[i] = true;
+            state.g.can_raise[i] = true;
         } 
     
     }
 
-    state.g.bet_amount = 0;
+    state.g.bet_amount = Chips::zero();
+    state.g.last_raise = street_bet_unit(&state.g);
+    state.g.bets_this_street = 0;
+    state.g.last_aggressor = 0;
+    Ok(())
 
 }
 
-fn deal_new_hand(state: &mut UserState<State>) {
+fn deal_new_hand(state: &mut UserState<State>) -> Result<(), Box<Error>> {
     
-    // Reset all player status
-    state.g.needs_action = vec![true; NUM_PLAYERS];
-    state.g.still_in = vec![true; NUM_PLAYERS];
-    state.g.hand_over = false;
-    state.g.last_move = String::from("New Hand");
+    seat_players(state)?;
+
+    // Reset all player status, busted players sit out
+    for i in 0..num_players(state) {
+        let has_chips = !state.g.chips[i].is_zero();
+        state.g.needs_action[i] = has_chips;
+        state.g.can_raise[i] = has_chips;
+        state.g.still_in[i] = has_chips;
+    }
+    state.g.hand_contributions = vec![Chips::zero(); num_players(state)];
+    state.g.bet_amount = Chips::zero();
+    state.g.last_raise = street_bet_unit(&state.g);
+    state.g.bets_this_street = 0;
+    state.g.last_aggressor = 0;
+    state.g.shown = vec![false; num_players(state)];
+    state.g.street = Street::Preflop;
+    state.g.hand_number += 1;
+    state.g.card_table = Vec::new();
+    state.g.hand_result = String::from("");
+    state.g.events = Vec::new();
 
-    let seed = state.ctx.seed.unwrap();
+    let started = HandEvent::HandStarted {
+        hand_number: state.g.hand_number,
+        dealer: state.g.dealer,
+        stacks: state.g.chips.clone()
+    };
+    log_event(state, started);
 
-    let cards = &mut state.g.cards.unwrap();
+    post_forced_bets(state)?;
 
-    // Create initial hand of 2 for all players
-    for player in 0..NUM_PLAYERS {
+    // One shuffle per hand, every card of the hand comes off this deck
+    let seed = state.g.entropy.as_ref().ok_or(Box::new(Errors::NoEntropy))?.hand_seed(state.g.hand_number);
+    let mut deck = state.g.config.shuffle.source().deck(seed);
 
-        let player_hand = draw_cards(cards, seed, 2);
-        state.g.hands[player] = player_hand;
-    }
-    
-}
+    // Commit to the deck before any card leaves it, the seed is revealed with the hand history.
+    // Seeds are hashed from the table's entropy, so a revealed seed says nothing about the next hand.
+    state.g.deck_commitment = Some(commitment::commit(seed, &deck));
+    state.g.deck_seed = Some(commitment::seed_to_hex(seed));
 
-fn payout_hand(winner: &usize, state: &mut UserState<State>) {
-    
-    // Pay the winner
-    state.g.bet_amount = 0;
-    state.g.hand_pot += state.g.chip_table.iter().sum::<u16>();
-    state.g.chips[*winner] += state.g.hand_pot;
-    state.g.chip_table = vec![0; NUM_PLAYERS];
-    state.g.hand_pot = 0;
-    state.g.last_move.push_str(&" - HAND OVER");
-    
-    // Give all remaining players a turn to confirm
-    for i in 0..NUM_PLAYERS {
-        if state.g.still_in[i] {
-            state.g.needs_action[i] = true;
+    // Deal two hole cards to every player in the hand, one at a time starting left of the dealer
+    let dealer_idx = state.g.dealer as usize - 1;
+    state.g.hands = vec![Vec::new(); num_players(state)];
+
+    for _ in 0..2 {
+        for offset in 1..num_players(state) + 1 {
+
+            let player = (dealer_idx + offset) % num_players(state);
+            if state.g.still_in[player] {
+                let card = deck.deal()?;
+                state.g.hands[player].push(card);
+            }
         }
     }
 
+    state.g.deck = Some(deck);
+    state.g.to_act = first_to_act(state);
+    Ok(())
+    
 }
 
-fn reset_hand(state: &mut UserState<State>) {
-
-    // Advance the dealer
-    state.g.dealer = (state.g.dealer % state.ctx.num_players) + 1;
+// Burn a card, then deal the flop, the turn or the river from the hand's deck
+fn deal_next_street(state: &mut UserState<State>) -> Result<(), Box<Error>> {
 
-    while state.g.chips[state.g.dealer as usize - 1] <= 0 {
-        state.g.dealer = (state.g.dealer % state.ctx.num_players) + 1;
+    let street = state.g.street.next();
+    if !street.is_betting() {
+        return Err(Box::new(Errors::InvalidMove));
     }
 
-    // Clear the table and shuffle the deck
-    state.g.hand_over = true;
-    state.g.card_table = Vec::new();
-    state.g.hands = vec![Vec::new(), Vec::new()];
-    state.g.cards = Some([[true; NUM_DECK_VALUES]; NUM_DECK_SUITS]);
-    state.g.still_in = vec![false; NUM_PLAYERS];
+    let deck = state.g.deck.as_mut().ok_or(Box::new(Errors::DeckExhausted))?;
+    deck.burn()?;
+    let cards = deck.deal_many(street.cards_dealt())?;
 
+    state.g.card_table.extend(cards.iter().cloned());
+    state.g.street = street;
+    log_event(state, HandEvent::StreetDealt { street: street, cards: cards });
+    Ok(())
 }
 
-/// Define your moves as methods in this trait.
-#[moves]
-trait Moves {
+/**
+ * First player to act on the current street. Preflop the action starts left of the
+ * big blind, which heads-up is the button. After the flop it starts left of the button,
+ * so heads-up the button acts last. Seats that are folded, all in or busted are skipped.
+ */
+fn first_to_act(state: &UserState<State>) -> u16 {
+
+    let after_seat = match state.g.street {
+        Street::Preflop => state.g.big_blind_seat,
+        _ => state.g.dealer
+    } as usize;
+
+    // Seats are 1-indexed, so the seat after is also the 0-indexed start
+    for offset in 0..num_players(state) {
+        let seat = (after_seat + offset) % num_players(state);
+        if state.g.needs_action[seat] {
+            return seat as u16 + 1;
+        }
+    }
 
-    fn poker_move(state: &mut UserState<State>, _player_id: u16, args: &Option<Value>)
-                -> Result<(), Box<Error>> {
+    state.g.dealer
+}
 
-        if let Some(value) = args {
-            let action: u64 = value.as_array()
-                .and_then(|arr| arr.get(0))
-                .and_then(|click| click.as_u64())
-                .ok_or(Box::new(Errors::InvalidMove))?;
-
-            let player_idx = state.ctx.action_players.clone().expect("No acting players found")[0] as usize - 1;
-
-            match action {
-                
-                // Check or Call
-                1 => {
-                    
-                    let needed_bet = state.g.bet_amount - state.g.chip_table[player_idx];
-                    state.g.chip_table[player_idx] += needed_bet;
-                    state.g.chips[player_idx] -= needed_bet;
-
-                    state.g.needs_action[player_idx] = false;
-                    
-                    // UI Output
-                    if needed_bet == 0 {
-                        state.g.last_move = String::from("Check");
-                    } else {
-                        let mut move_log = String::from("Call ");
-                        move_log.push_str(&state.g.bet_amount.to_string());
-                        state.g.last_move = move_log;
-                    }
-                    
-                    return Ok(());
-                },
-
-                // Standard bet/raise
-                2 => {
-                    // Raise the required bet
-                    if state.g.bet_amount == 0 {
-                        
-                        state.g.bet_amount = STANDARD_BET_SIZE;
-                        state.g.last_move = String::from(format!("Bet {}", state.g.bet_amount));
-
-                    } else {
-                        
-                        state.g.bet_amount *= 2;
-                        state.g.last_move = String::from(format!("Raise {}", state.g.bet_amount));
-                    }
-                    
-                    // Since the bet has been raised, everyone who is still in the hand needs action.
-                    // Check if a player needs to go all in
-                    for i in 0..NUM_PLAYERS {
-                        if state.g.still_in[i] {
-
-                            if (state.g.chips[i] + state.g.chip_table[i]) <= state.g.bet_amount {
-                                state.g.bet_amount = state.g.chips[i] + state.g.chip_table[i];
-                                state.g.last_move = String::from(format!("All In {}", state.g.bet_amount));
-                            }
-
-                            state.g.needs_action[i] = true;
-                        }
-                    }
-
-                    // Pay for it, match the bet amount
-                    let needed_bet = state.g.bet_amount - state.g.chip_table[player_idx];
-                    state.g.chip_table[player_idx] += needed_bet;
-                    state.g.chips[player_idx] -= needed_bet;
-
-                    state.g.needs_action[player_idx] = false;
-                    return Ok(());
+/**
+ * Run every transition that is due, after a move or when a turn begins.
+ * This is the only place the street changes: once the betting is over an uncalled bet goes
+ * back to its player, a hand with one player left is won by fold, a finished betting round
+ * deals the next street, the board runs out when nobody can bet,
+ * the pots are paid once every player at the showdown showed or mucked,
+ * and a complete hand deals the next one straight away.
+ */
+fn advance_hand(state: &mut UserState<State>) -> Result<(), Box<Error>> {
+
+    loop {
+        match state.g.street {
+
+            Street::HandComplete => {
 
-                },
-
-                // All in
-                3 => {
-                    
-                    // Try betting your whole stack.
-                    state.g.bet_amount = state.g.chips[player_idx];
-
-                    // Since the bet has been raised, everyone who is still in the hand needs action.
-                    // Check if a different player needs to go all in
-                    for i in 0..NUM_PLAYERS {
-                        if state.g.still_in[i] {
-
-                            if (state.g.chips[i] + state.g.chip_table[i]) <= state.g.bet_amount {
-                                state.g.bet_amount = state.g.chips[i] + state.g.chip_table[i];
-                            }
-
-                            state.g.needs_action[i] = true;
-                        }
-                    }
-                    
-                    // Needed in case someone else is pushed all in.
-                    let needed_bet = state.g.bet_amount - state.g.chip_table[player_idx];
-                    state.g.chip_table[player_idx] += needed_bet;
-                    state.g.chips[player_idx] -= needed_bet;
-
-                    state.g.last_move = String::from(format!("All In {}", state.g.bet_amount));
-                    state.g.needs_action[player_idx] = false;
+                // The game is over once a single player has chips
+                if count_seats_with_chips(state) < 2 {
                     return Ok(());
+                }
 
-                },
+                if state.g.hand_number > 0 {
+                    reset_hand(state);
+                }
+                place_button(state);
+                deal_new_hand(state)?;
+            },
 
-                // Fold
-                0 => {
-                    
-                    if !state.g.still_in[player_idx] {
-                        return Err(Box::new(Errors::InvalidMove));
-                    }
+            Street::Showdown => {
 
-                    state.g.last_move = String::from("Fold");
-                    state.g.still_in[player_idx] = false;
-                    state.g.needs_action[player_idx] = false;
+                if state.g.needs_action.iter().any(|needs_action| *needs_action) {
                     return Ok(());
-                },
-
-                // Confirm hand and proceed
-                99 => {
+                }
+                showdown(state)?;
+            },
 
-                    state.g.last_move = String::from("HAND OVER");
-                    state.g.needs_action[player_idx] = false;
+            street => {
 
-                    if betting_round_is_over(state) {
-                        reset_hand(state);
-                    }
+                let (is_over, fold_winner) = hand_is_over_folded(state);
 
+                if !is_over && !betting_round_is_over(state) && !betting_is_closed(state) {
                     return Ok(());
+                }
 
+                return_uncalled_bet(state)?;
+                if is_over {
+                    win_by_fold(state, fold_winner)?;
+                } else if street == Street::River {
+                    start_showdown(state);
+                } else {
+                    deal_next_street(state)?;
+                    next_betting_round(state)?;
+                    state.g.to_act = first_to_act(state);
                 }
+            }
+        }
+    }
+}
 
-                _ => return Err(Box::new(Errors::InvalidMove)),
+/**
+ * Decide who shows at the showdown. The last player to bet or raise on the river shows
+ * first, or the first player left of the button when it was checked through. Players who
+ * are all in are shown automatically, the others show or muck in turn after the first.
+ */
+fn start_showdown(state: &mut UserState<State>) {
+
+    state.g.street = Street::Showdown;
+    state.g.needs_action = vec![false; num_players(state)];
+
+    let first = match state.g.last_aggressor {
+        seat if seat != 0 && state.g.still_in[seat as usize - 1] => seat as usize - 1,
+        _ => {
+            let dealer_idx = state.g.dealer as usize - 1;
+            let mut seat = (dealer_idx + 1) % num_players(state);
+            while !state.g.still_in[seat] && seat != dealer_idx {
+                seat = (seat + 1) % num_players(state);
             }
+            seat
+        }
+    };
 
+    for offset in 0..num_players(state) {
+        let seat = (first + offset) % num_players(state);
+        if !state.g.still_in[seat] {
+            continue;
         }
 
-        Ok(())
+        if offset == 0 || state.g.chips[seat].is_zero() {
+            show_hand(state, seat);
+        } else {
+            state.g.needs_action[seat] = true;
+        }
     }
+
+    state.g.to_act = next_to_act(state, first);
 }
 
-/// Define the game flow.
-#[flow]
-trait Flow {
+// Turn a player's hole cards face up for everyone
+fn show_hand(state: &mut UserState<State>, player_idx: usize) {
+    state.g.shown[player_idx] = true;
+    let cards = state.g.hands[player_idx].clone();
+    log_event(state, HandEvent::Shown { player: player_idx as u16 + 1, cards: cards });
+}
 
-    fn initial_state(&self, _seed: Option<u128>) -> State {    
-    
-        let initial_deck = Some([[true; NUM_DECK_VALUES]; NUM_DECK_SUITS]);
+// Every seat's hole cards if they were shown, empty otherwise
+fn shown_hands(state: &State) -> Vec<Vec<Card>> {
+    state.hands.iter().zip(state.shown.iter())
+        .map(|(hand, shown)| if *shown { hand.clone() } else { Vec::new() })
+        .collect()
+}
 
-        State {
-            cards: initial_deck,
-            hands: vec![Vec::new(), Vec::new()],
-            needs_action: vec![true; NUM_PLAYERS as usize],
-            still_in: vec![true; NUM_PLAYERS as usize],
-            dealer: 1,
-            card_table: Vec::new(),
-            chips: vec![64; NUM_PLAYERS as usize],
-            chip_table: vec![0; NUM_PLAYERS as usize],
-            bet_amount: 0,
-            hand_pot: 0,
-            hand_over: true,
-            last_move: String::from("New Hand"),
-            hand_result: String::from("")
+// Chips a player bet that nobody matched go back to their stack once the betting is over
+fn return_uncalled_bet(state: &mut UserState<State>) -> Result<(), Box<Error>> {
+
+    let (player_idx, uncalled) = match pots::uncalled_bet(&state.g.hand_contributions) {
+        Some(uncalled) => uncalled,
+        None => return Ok(())
+    };
+
+    // Every earlier street was called, so the uncalled chips are all on the table
+    let uncalled = uncalled.min(state.g.chip_table[player_idx]);
+    state.g.chips[player_idx] = state.g.chips[player_idx].checked_add(uncalled)
+        .ok_or(Box::new(Errors::ChipOverflow))?;
+    state.g.chip_table[player_idx] = state.g.chip_table[player_idx].saturating_sub(uncalled);
+    state.g.hand_contributions[player_idx] = state.g.hand_contributions[player_idx].saturating_sub(uncalled);
+    state.g.bet_amount = state.g.chip_table.iter().cloned().max().unwrap_or(Chips::zero());
+
+    log_event(state, HandEvent::UncalledBetReturned { player: player_idx as u16 + 1, amount: uncalled });
+    Ok(())
+}
+
+// Everyone else folded, the last player takes every pot without showing
+fn win_by_fold(state: &mut UserState<State>, winner: usize) -> Result<(), Box<Error>> {
+
+    let awards: Vec<(usize, Chips)> = hand_pots(state).iter().map(|pot| (winner, pot.amount)).collect();
+
+    for (pot_idx, &(winner, amount)) in awards.iter().enumerate() {
+        log_event(state, HandEvent::PotAwarded { pot: pot_idx, player: winner as u16 + 1, amount: amount, ranking: None });
+    }
+    payout_hand(&awards, state)
+}
+
+// Each pot goes to the best hands among the players who covered it
+fn showdown(state: &mut UserState<State>) -> Result<(), Box<Error>> {
+
+    let rankings = rank_hands(state);
+    let button = state.g.dealer as usize - 1;
+    let mut awards = Vec::new();
+
+    for (pot_idx, pot) in hand_pots(state).iter().enumerate() {
+
+        let winners = pot_winners(pot, &rankings);
+        if winners.is_empty() {
+            continue;
+        }
+
+        let winner_idxs: Vec<usize> = winners.iter().map(|winner| winner.player).collect();
+
+        for (winner, amount) in pots::split_pot(pot, &winner_idxs, button, num_players(state)) {
+            let ranking = rankings[winner].clone();
+            log_event(state, HandEvent::PotAwarded { pot: pot_idx, player: winner as u16 + 1, amount: amount, ranking: ranking });
+            awards.push((winner, amount));
+        }
+    }
+
+    payout_hand(&awards, state)
+}
+
+fn hand_pots(state: &UserState<State>) -> Vec<Pot> {
+    pots::build_pots(&state.g.hand_contributions, &state.g.still_in)
+}
+
+// Evaluate every player still in the hand, pots are resolved separately using these rankings
+fn rank_hands(state: &UserState<State>) -> Vec<Option<CardRanking>> {
+
+    let mut rankings = Vec::new();
+
+    for i in 0..num_players(state) {
+
+        if !state.g.still_in[i] {
+            rankings.push(None);
+            continue;
         }
 
+        let mut working_hand = state.g.hands[i].clone();
+        working_hand.extend_from_slice(&state.g.card_table);
+        let hand_rank = resolver::evaluate_best_hand(i, &working_hand);
+
+        println!("Player {} best hand is {}-{:?}", hand_rank.player, 
+            resolver::hand_to_string(&hand_rank.hand), hand_rank.tiebreak);
+
+        rankings.push(Some(hand_rank));
     }
 
-    fn on_turn_begin(&self, state: &mut UserState<State>) 
-        -> Result<(), Box<Error>> {
+    rankings
+}
 
-        let seed = state.ctx.seed.unwrap();
+// Find every player holding the best hand among those eligible for a pot
+fn pot_winners(pot: &Pot, rankings: &Vec<Option<CardRanking>>) -> Vec<CardRanking> {
 
-        if state.g.hand_over {
-            deal_new_hand(state);
-        } 
+    let mut winners: Vec<CardRanking> = Vec::new();
 
-        if betting_round_is_over(state) {
+    for player in &pot.eligible {
+        if let Some(ref hand_rank) = rankings[*player] {
 
-            println!("Betting round is over for player {:?}", state.ctx.action_players);
+            let ordering = match winners.first() {
+                Some(best) => hand_rank.cmp(best),
+                None => Ordering::Greater
+            };
 
-            // Deal cards if needed
-            match state.g.card_table.len() {
-                // Flop
-                0 => {
-                    state.g.card_table = draw_cards(&mut state.g.cards.unwrap(), seed, 3);
-                    next_betting_round(state);
-                },
-                // Turn
-                3 => {
-                    state.g.card_table.append(&mut draw_cards(&mut state.g.cards.unwrap(), seed, 1));
-                    next_betting_round(state);
-                },
-                // River
-                4 => {
-                    state.g.card_table.append(&mut draw_cards(&mut state.g.cards.unwrap(), seed, 1));
-                    next_betting_round(state);
-                },
-                _ => return Err(Box::new(Errors::InvalidMove)),
-            } 
+            match ordering {
+                Ordering::Greater => winners = vec![hand_rank.clone()],
+                Ordering::Equal => winners.push(hand_rank.clone()),
+                Ordering::Less => ()
+            }
         }
+    }
 
-        Ok(())
+    winners
+}
 
+// Pays out the chips won by each player and clears the table
+fn payout_hand(awards: &Vec<(usize, Chips)>, state: &mut UserState<State>) -> Result<(), Box<Error>> {
+    
+    // Pay the winners
+    let mut chips = state.g.chips.clone();
+    for &(winner, amount) in awards {
+        chips[winner] = chips[winner].checked_add(amount).ok_or(Box::new(Errors::ChipOverflow))?;
     }
+    state.g.chips = chips;
+    state.g.bet_amount = Chips::zero();
+    state.g.chip_table = vec![Chips::zero(); num_players(state)];
+    state.g.hand_contributions = vec![Chips::zero(); num_players(state)];
+    state.g.hand_pot = Chips::zero();
+    state.g.street = Street::HandComplete;
+    log_event(state, HandEvent::HandEnded);
+    state.g.hand_result = events::describe_result(&state.g.events);
+
+    // The history is kept until the next hand is over
+    state.g.last_hand = Some(HandHistory::record(&state.g));
+
+    // Nobody acts between hands, the next hand is dealt when the hand advances
+    state.g.needs_action = vec![false; num_players(state)];
+    state.g.to_act = 0;
+    Ok(())
 
-    fn on_move(&self, state: &mut UserState<State>, _: &Move) -> Result<(), Box<Error>> {
+}
 
-        // Manually advance the random seed
-        let bumpable_seed = state.ctx.seed.expect("");
-        state.ctx.seed = Some(bumpable_seed + 1);
+fn reset_hand(state: &mut UserState<State>) {
 
-        // End hand via fold
-        let (is_over, fold_winner) = hand_is_over_folded(state);
-        if is_over {
-            payout_hand(&fold_winner, state);
-            state.g.hand_result = String::from(format!("Player {} wins by fold", fold_winner + 1));
-            return Ok(());
+    // Keep the result on show into the next hand if the table wants it displayed
+    match state.g.config.hand_transition {
+        HandTransition::Display { moves } if moves > 0 => {
+            state.g.last_hand_result = state.g.hand_result.clone();
+            state.g.last_board = state.g.card_table.clone();
+            state.g.last_shown_hands = shown_hands(&state.g);
+            state.g.result_moves_left = moves;
+        },
+        _ => {
+            state.g.last_hand_result = String::from("");
+            state.g.last_board = Vec::new();
+            state.g.last_shown_hands = Vec::new();
+            state.g.result_moves_left = 0;
         }
-        
-        // End hand via evaluation
-        if betting_round_is_over(state) {
-            
-            // Automatically deal all cards if a player has no chips
-            if is_all_in_hand(state) && state.g.card_table.len() < 5 {
-                let seed = state.ctx.seed.unwrap();
-                let cards_needed = 5 - state.g.card_table.len() as u8;
-                state.g.card_table.append(&mut draw_cards(&mut state.g.cards.unwrap(), seed, cards_needed));
-            }
-            
-            if state.g.card_table.len() == 5 {
-            
-                // Store the winning hand
-                let mut winner = CardRanking{
-                    player: 0,
-                    hand: 0,
-                    tiebreak: vec![0]
-                    };
-
-                for i in 0..(state.ctx.num_players as usize) {
-                    
-                    if state.g.still_in[i] {
-                        
-                        let mut working_hand = state.g.hands[i].clone();
-                        working_hand.extend_from_slice(&state.g.card_table);
-                        let hand_rank = resolver::evaluate_best_hand(i, &working_hand);
-                        
-                        println!("Player {} best hand is {}-{:?}", hand_rank.player, 
-                            resolver::hand_to_string(&hand_rank.hand), hand_rank.tiebreak);
-
-                        if hand_rank.hand > winner.hand {
-                            winner = hand_rank;
-                        } else if hand_rank.hand == winner.hand {
-
-                            // Tiebreakers
-                            for j in 0..hand_rank.tiebreak.len() {
-                                if hand_rank.tiebreak[j] > winner.tiebreak[j] {
-                                    winner = hand_rank;
-                                    break;
-                                } else if hand_rank.tiebreak[j] < winner.tiebreak[j] {
-                                    break;
-                                }
-                            }
-                            
-                            // TODO: Hand is completely tied, do a split pot.
-                        
-                        }
-                        
-                    }
-                }
+    }
 
-                payout_hand(&winner.player, state);
+    // Clear the table, the next hand is dealt from a new deck
+    state.g.card_table = Vec::new();
+    state.g.hands = vec![Vec::new(); num_players(state)];
+    state.g.deck = None;
+    state.g.deck_commitment = None;
+    state.g.deck_seed = None;
+    state.g.still_in = vec![false; num_players(state)];
 
-                let hand_label = resolver::hand_to_string(&winner.hand);
-                println!("Hand was won by player {} with hand ranking {}", winner.player + 1, hand_label);
-                state.g.hand_result = String::from(format!("Player {} wins with {}", winner.player + 1, hand_label));
+}
 
-            }
+// Bet or raise to a size, going all in for less than the current bet is a call
+fn bet_or_raise(state: &mut UserState<State>, player_idx: usize, size: BetSize) -> Result<(), Box<Error>> {
+
+    if size == BetSize::AllIn && raise_context(&state.g, player_idx).all_in_to() <= state.g.bet_amount {
+        let needed_bet = state.g.chips[player_idx];
+        commit_chips(state, player_idx, needed_bet)?;
+        state.g.needs_action[player_idx] = false;
+        let to = state.g.chip_table[player_idx];
+        log_event(state, HandEvent::Called { player: player_idx as u16 + 1, to: to, all_in: true });
+        return Ok(());
+    }
+
+    // Only a full raise reopens the betting for players who already acted
+    if !state.g.can_raise[player_idx] {
+        return Err(Box::new(Errors::InvalidMove));
+    }
+
+    let raise_to = betting::resolve_raise(&size, &raise_context(&state.g, player_idx), &state.g.config.betting_structure)
+        .ok_or(Box::new(Errors::InvalidMove))?;
+    let opening_bet = state.g.bet_amount.is_zero();
+
+    raise_bet(state, player_idx, raise_to)?;
+
+    let player = player_idx as u16 + 1;
+    let all_in = state.g.chips[player_idx].is_zero();
+    log_event(state, match opening_bet {
+        true => HandEvent::Bet { player: player, to: raise_to, all_in: all_in },
+        false => HandEvent::Raised { player: player, to: raise_to, all_in: all_in }
+    });
+
+    Ok(())
+}
+
+/**
+ * Apply a decoded action for the player to act.
+ * The action is validated first, an invalid action leaves the state untouched.
+ */
+fn apply_action(state: &mut UserState<State>, player_id: u16, action: &PokerAction) -> Result<(), Box<Error>> {
+
+    legal::validate_action(&state.g, player_id, action)?;
+    let player_idx = player_id as usize - 1;
+
+    play_action(state, player_idx, action)?;
+
+    // The turn passes to the left, a new street or hand picks its own first player
+    state.g.to_act = next_to_act(state, player_idx);
+    count_down_result(state);
+    Ok(())
+}
+
+// The last hand's result is only kept on show for a few moves of the next hand
+fn count_down_result(state: &mut UserState<State>) {
+
+    if state.g.result_moves_left == 0 {
+        return;
+    }
+
+    state.g.result_moves_left -= 1;
+    if state.g.result_moves_left == 0 {
+        state.g.last_hand_result = String::from("");
+        state.g.last_board = Vec::new();
+        state.g.last_shown_hands = Vec::new();
+    }
+}
+
+// Record what just happened in the hand, the last move shown to players is rendered from the log
+fn log_event(state: &mut UserState<State>, event: HandEvent) {
+    state.g.events.push(event);
+    state.g.last_move = events::describe_last_move(&state.g.events);
+}
 
+// Next player after player_idx who still needs to act, 0 if the round is over
+fn next_to_act(state: &UserState<State>, player_idx: usize) -> u16 {
+
+    for offset in 1..num_players(state) + 1 {
+        let seat = (player_idx + offset) % num_players(state);
+        if state.g.needs_action[seat] {
+            return seat as u16 + 1;
         }
+    }
+
+    0
+}
 
-        Ok(())
+fn play_action(state: &mut UserState<State>, player_idx: usize, action: &PokerAction) -> Result<(), Box<Error>> {
+
+    // Any other action closes the straddle
+    if *action != PokerAction::Straddle {
+        state.g.can_straddle = false;
     }
 
-    fn get_current_players(&self, state: &UserState<State>) -> Option<Vec<u16>> {
+    match *action {
 
-        let mut next_to_play = Vec::new();
+        // Check or Call, a short stack calls all in for less
+        PokerAction::Check | PokerAction::Call => {
 
-        let action_player = match state.ctx.action_players {
-            Some(ref players) => players[0],
-            None => 1,
-        };
+            let to_call = state.g.bet_amount.saturating_sub(state.g.chip_table[player_idx]);
+            let needed_bet = to_call.min(state.g.chips[player_idx]);
+            commit_chips(state, player_idx, needed_bet)?;
 
-        let mut next_candidate = (action_player + 1) % state.ctx.num_players;
-        let mut move_counter = 0;
+            state.g.needs_action[player_idx] = false;
 
-        // Not 0-indexed, required to be 1 or 2
-        if state.g.hand_over || betting_round_is_over(state) {
-            next_to_play.push(state.g.dealer);
-            return Some(next_to_play);
-        } 
+            let player = player_idx as u16 + 1;
+            let event = match needed_bet.is_zero() {
+                true => HandEvent::Checked { player: player },
+                false => HandEvent::Called {
+                    player: player,
+                    to: state.g.chip_table[player_idx],
+                    all_in: state.g.chips[player_idx].is_zero()
+                }
+            };
+            log_event(state, event);
+            Ok(())
+        },
+
+        // Bet or raise to an exact amount
+        PokerAction::Bet { to } | PokerAction::Raise { to } => {
+            bet_or_raise(state, player_idx, BetSize::To(to))
+        },
+
+        PokerAction::BetPreset { preset } => {
+            let size = match preset {
+                Preset::Min => BetSize::MinRaise,
+                Preset::HalfPot => BetSize::HalfPot,
+                Preset::Pot => BetSize::Pot
+            };
+            bet_or_raise(state, player_idx, size)
+        },
 
-        while next_to_play.is_empty() {          
+        PokerAction::AllIn => bet_or_raise(state, player_idx, BetSize::AllIn),
 
-            if state.g.needs_action[next_candidate as usize] {
-                next_to_play.push(next_candidate + 1);
-                break;
-            } 
-            
-            next_candidate = (next_candidate + 1) % state.ctx.num_players;
-            move_counter += 1;
+        // Voluntary straddle, posts twice the big blind and acts last preflop
+        PokerAction::Straddle => {
 
-            if move_counter > state.ctx.num_players {
-                panic!("Game state stuck in finding next players. Acting player {}", action_player)
-            }
+            let straddle = state.g.config.forced_bets.big_blind.saturating_mul(2);
+            post_blind(state, player_idx, Blind::Straddle, straddle)?;
+
+            // The straddle plays as the big blind, so a raise has to add at least the whole straddle
+            state.g.last_raise = straddle;
+            state.g.bets_this_street = state.g.bets_this_street.saturating_add(1);
+
+            state.g.can_straddle = false;
+            Ok(())
+        },
 
+        PokerAction::Fold => {
+
+            state.g.still_in[player_idx] = false;
+            state.g.needs_action[player_idx] = false;
+            log_event(state, HandEvent::Folded { player: player_idx as u16 + 1 });
+            Ok(())
+        },
+
+        // At the showdown, table the hand or give up the pot without showing it
+        PokerAction::Show => {
+
+            show_hand(state, player_idx);
+            state.g.needs_action[player_idx] = false;
+            Ok(())
+        },
+
+        PokerAction::Muck => {
+
+            state.g.still_in[player_idx] = false;
+            state.g.needs_action[player_idx] = false;
+            log_event(state, HandEvent::Mucked { player: player_idx as u16 + 1 });
+            Ok(())
         }
+    }
+}
 
-        Some(next_to_play)
-    }  
-   
+/**
+ * A new table seated from config, waiting for the first hand to be dealt.
+ * Every hand is shuffled with a seed derived from the table's entropy.
+ */
+fn new_table(config: TableConfig, entropy: TableEntropy) -> State {
 
-    fn end_turn_if(&self, _: &UserState<State>) -> bool {
-        true
+    let mut state = State {
+        config: config,
+        entropy: Some(entropy),
+        ..State::default()
+    };
+    seat_table(&mut state);
+
+    state
+}
+
+// The moves and the flow only call the functions below, so a replay runs what the game server runs
+fn make_move(state: &mut UserState<State>, player_id: u16, args: &Option<Value>) -> Result<(), Box<Error>> {
+
+    if let Some(value) = args {
+        let action = actions::decode_move(value)?;
+
+        let is_acting = match state.ctx.action_players {
+            Some(ref players) => players.contains(&player_id),
+            None => false
+        };
+        if !is_acting {
+            return Err(Box::new(Errors::NotYourTurn(player_id)));
+        }
+
+        apply_action(state, player_id, &action)?;
+        return check_chips_conserved(&state.g);
     }
 
-    fn end_game_if(&self, state: &UserState<State>) -> Option<(Option<Score>, Value)> {
+    Ok(())
+}
 
-        let mut players_in = 0;
-        let mut winner_idx: u16 = 0;
+fn begin_turn(state: &mut UserState<State>) -> Result<(), Box<Error>> {
 
-        for i in 0..NUM_PLAYERS {
-            
-            // To be in, you must have chips or still be alive in a hand
-            if state.g.chips[i] > 0 || state.g.still_in[i] {
-                players_in += 1;
-                winner_idx = i as u16;
-            }
+    advance_hand(state)?;
+
+    check_chips_conserved(&state.g)
+}
+
+fn finish_move(state: &mut UserState<State>) -> Result<(), Box<Error>> {
+
+    advance_hand(state)?;
+
+    check_chips_conserved(&state.g)
+}
+
+fn current_players(state: &UserState<State>) -> Option<Vec<u16>> {
+
+    // Between hands, the first to act in the next hand, which is dealt when the turn begins
+    if state.g.street == Street::HandComplete {
+        let mut next_hand = state.clone();
+        return match advance_hand(&mut next_hand) {
+            Ok(()) if next_hand.g.to_act != 0 => Some(vec![next_hand.g.to_act]),
+            _ => Some(vec![state.g.dealer])
+        };
+    }
+
+    // The engine passes the turn as it applies moves and deals new streets.
+    // Not 0-indexed, seats are 1 to num_players
+    match state.g.to_act {
+        0 => Some(vec![state.g.dealer]),
+        to_act => Some(vec![to_act])
+    }
+}
+
+fn game_over(state: &UserState<State>) -> Option<(Option<Score>, Value)> {
+
+    // Nobody has been seated yet
+    if state.g.chips.is_empty() {
+        return None;
+    }
+
+    let mut players_in = 0;
+    let mut winner_idx: u16 = 0;
+
+    for i in 0..num_players(state) {
+        
+        // To be in, you must have chips or still be alive in a hand that is being played
+        let alive_in_hand = state.g.still_in[i] && state.g.street != Street::HandComplete;
+        if !state.g.chips[i].is_zero() || alive_in_hand {
+            players_in += 1;
+            winner_idx = i as u16;
         }
+    }
+
+    if players_in == 1 {
+        return Some((Some(Score::Win(winner_idx + 1)), json!({
+            "winner": winner_idx + 1
+        })));
+    }
+
+    None
+
+}
+
+/// Define your moves as methods in this trait.
+#[moves]
+trait Moves {
+
+    fn poker_move(state: &mut UserState<State>, player_id: u16, args: &Option<Value>)
+                -> Result<(), Box<Error>> {
+        make_move(state, player_id, args)
+    }
+}
+
+/// Define the game flow.
+#[flow]
+trait Flow {
+
+    fn initial_state(&self, seed: Option<u128>) -> State {    
+    
+        // The players' entropy seeds every hand, a game created without it has only its own seed
+        let entropy = entropy::table_entropy().unwrap_or_else(|| TableEntropy::from_seed(seed.unwrap_or(0)));
 
-        if players_in == 1 {
-            return Some((Some(Score::Win(winner_idx + 1)), json!({
-                "winner": winner_idx + 1
-            })));
+        // Tables are seated from the config the game was created with, or start from a scenario
+        match scenario::scenario() {
+            Some(scenario) => scenario.build(&entropy).expect("Invalid scenario"),
+            None => new_table(config::table_config(), entropy)
         }
 
-        None
+    }
 
+    fn on_turn_begin(&self, state: &mut UserState<State>) 
+        -> Result<(), Box<Error>> {
+        begin_turn(state)
+    }
+
+    fn on_move(&self, state: &mut UserState<State>, _: &Move) -> Result<(), Box<Error>> {
+        finish_move(state)
+    }
+
+    fn get_current_players(&self, state: &UserState<State>) -> Option<Vec<u16>> {
+        current_players(state)
+    }  
+   
+
+    fn end_turn_if(&self, _: &UserState<State>) -> bool {
+        true
+    }
+
+    fn end_game_if(&self, state: &UserState<State>) -> Option<(Option<Score>, Value)> {
+        game_over(state)
     }
 
     fn optimistic_update(&self,  state: &UserState<State>, game_move: &Move) -> bool {
@@ -605,12 +1362,16 @@ trait Flow {
     fn player_filter(&self) -> Option<fn(&State, u16) -> State> {
         Some(|state, player_id| {
             let mut new_state = state.clone();
-            new_state.cards = None;
-            new_state.hands = match player_id {
-                1 => new_state.hands[..1].into(),
-                2 => new_state.hands[1..].into(),
-                _ => vec![]
-            };
+            new_state.entropy = None;
+            new_state.deck = None;
+            new_state.deck_seed = None;
+
+            // Only the player's own seat and hands shown at the showdown keep their cards
+            for (i, hand) in new_state.hands.iter_mut().enumerate() {
+                if i + 1 != player_id as usize && !state.shown.get(i).cloned().unwrap_or(false) {
+                    *hand = Vec::new();
+                }
+            }
             new_state
         })
     }
@@ -618,8 +1379,324 @@ trait Flow {
 
 #[cfg(test)]
 mod tests{
+    use super::*;
+    use actions::PokerMove;
+    use scenario::{Scenario, Seat};
+
+    fn cards(names: &str) -> Vec<Card> {
+        names.split(' ').map(|name| resolver::card_from_string(name).unwrap()).collect()
+    }
+
+    fn seat(stack: u64, invested: u64, hole_cards: &str) -> Seat {
+        Seat { stack: Chips(stack), invested: Chips(invested), hole_cards: cards(hole_cards), ..Seat::default() }
+    }
+
+    fn start(table: State) -> UserState<State> {
+        let players: Vec<u16> = (1..table.config.num_seats + 1).collect();
+        UserState { ctx: Context::new(&players, None), g: table }
+    }
+
+    fn table(num_seats: u16) -> TableConfig {
+        TableConfig { num_seats: num_seats, ..TableConfig::default() }
+    }
+
+    // A new table with its first hand dealt
+    fn deal(config: TableConfig) -> UserState<State> {
+        let mut state = start(new_table(config, TableEntropy::from_seed(7)));
+        begin_turn(&mut state).unwrap();
+        state
+    }
+
+    // Play the move of the player to act the way the game server does
+    fn play(state: &mut UserState<State>, action: PokerAction) {
+        state.ctx.action_players = current_players(state);
+        begin_turn(state).unwrap();
+        let player_id = state.g.to_act;
+        make_move(state, player_id, &Some(json!([PokerMove::new(action).to_json()]))).unwrap();
+        finish_move(state).unwrap();
+    }
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    fn card_rankings_ignore_the_player() {
+        let flush = CardRanking { player: 0, hand: 5, tiebreak: vec![12, 9, 7, 4, 2] };
+        let same_flush = CardRanking { player: 1, hand: 5, tiebreak: vec![12, 9, 7, 4, 2] };
+        let lower_flush = CardRanking { player: 2, hand: 5, tiebreak: vec![12, 9, 7, 4, 1] };
+        let straight = CardRanking { player: 3, hand: 4, tiebreak: vec![12] };
+
+        assert_eq!(flush, same_flush);
+        assert!(flush > lower_flush);
+        assert!(lower_flush > straight);
+    }
+
+    #[test]
+    fn every_table_size_is_dealt() {
+        for num_seats in 3..MAX_PLAYERS + 1 {
+            let mut state = deal(table(num_seats));
+
+            let mut dealt: Vec<Card> = state.g.hands.iter().flat_map(|hand| hand.iter().cloned()).collect();
+            assert!(state.g.hands.iter().all(|hand| hand.len() == 2));
+            assert_eq!(state.g.deck.as_ref().unwrap().remaining(), 52 - 2 * num_seats as usize);
+            dealt.sort_by_key(|card| (card.suit, card.rank));
+            dealt.dedup();
+            assert_eq!(dealt.len(), 2 * num_seats as usize);
+
+            // Everyone folds to the big blind, and the next hand is dealt to every seat again
+            for _ in 0..num_seats - 1 {
+                play(&mut state, PokerAction::Fold);
+            }
+            assert_eq!(state.g.hand_number, 2);
+            assert_eq!(state.g.hands.iter().filter(|hand| hand.len() == 2).count(), num_seats as usize);
+        }
+    }
+
+    // A table between hands, after the first hand was played with these blinds
+    fn between_hands(config: TableConfig, stacks: Vec<u64>, blinds: (u16, u16, u16)) -> UserState<State> {
+        let mut table = new_table(config, TableEntropy::from_seed(7));
+        table.chips = stacks.into_iter().map(Chips).collect();
+        table.chips_in_play = Chips::checked_sum(table.chips.iter()).unwrap();
+        table.hand_number = 1;
+        let (dealer, small_blind, big_blind) = blinds;
+        table.dealer = dealer;
+        table.small_blind_seat = small_blind;
+        table.big_blind_seat = big_blind;
+
+        let mut state = start(table);
+        begin_turn(&mut state).unwrap();
+        state
+    }
+
+    #[test]
+    fn the_button_moves_past_busted_seats() {
+        // The big blind of the last hand busted
+        let state = between_hands(table(5), vec![64, 64, 0, 64, 64], (1, 2, 3));
+        assert_eq!((state.g.dealer, state.g.small_blind_seat, state.g.big_blind_seat), (2, 4, 5));
+        assert_eq!(state.g.to_act, 1);
+        assert!(state.g.hands[2].is_empty());
+    }
+
+    #[test]
+    fn a_dead_button_follows_the_big_blind() {
+        let mut config = table(5);
+        config.button_rule = ButtonRule::Dead;
+        let state = between_hands(config.clone(), vec![64, 64, 0, 64, 64], (1, 2, 3));
+
+        // The small blind is dead on the busted seat, nobody posts it
+        assert_eq!((state.g.dealer, state.g.small_blind_seat, state.g.big_blind_seat), (2, 3, 4));
+        assert_eq!(state.g.events[1..], [HandEvent::BlindPosted { player: 4, blind: Blind::Big, amount: Chips(2) }]);
+        assert_eq!(state.g.to_act, 5);
+
+        // Down to two players, the big blind moves on and the other player has the button
+        let state = between_hands(config, vec![64, 0, 0, 64, 0], (1, 2, 3));
+        assert_eq!((state.g.dealer, state.g.small_blind_seat, state.g.big_blind_seat), (1, 1, 4));
+        assert_eq!(state.g.to_act, 1);
+    }
+
+    #[test]
+    fn a_returning_player_posts_the_big_blind_they_missed() {
+        // The big blind skips the busted third seat
+        let mut state = between_hands(table(5), vec![64, 64, 0, 64, 64], (5, 1, 2));
+        assert_eq!((state.g.dealer, state.g.small_blind_seat, state.g.big_blind_seat), (1, 2, 4));
+        assert_eq!(state.g.missed_big_blind, vec![false, false, true, false, false]);
+
+        while state.g.hand_number < 3 {
+            play(&mut state, PokerAction::Fold);
+        }
+
+        // Back with chips, the player is dealt in on the button and posts the missed big blind
+        state.g.chips[2] = Chips(64);
+        state.g.chips_in_play = state.g.chips_in_play.saturating_add(Chips(64));
+        while state.g.hand_number < 4 {
+            play(&mut state, PokerAction::Fold);
+        }
+        assert_eq!((state.g.dealer, state.g.small_blind_seat, state.g.big_blind_seat), (3, 4, 5));
+        assert_eq!(state.g.events[1..], [
+            HandEvent::BlindPosted { player: 4, blind: Blind::Small, amount: Chips(1) },
+            HandEvent::BlindPosted { player: 5, blind: Blind::Big, amount: Chips(2) },
+            HandEvent::BlindPosted { player: 3, blind: Blind::Missed, amount: Chips(2) }
+        ]);
+        assert_eq!(state.g.missed_big_blind, vec![false; 5]);
+        assert_eq!(state.g.to_act, 1);
+    }
+
+    #[test]
+    fn heads_up_the_button_acts_first_preflop_and_last_after() {
+        let mut state = deal(table(2));
+        assert_eq!((state.g.dealer, state.g.small_blind_seat, state.g.big_blind_seat), (1, 1, 2));
+        assert_eq!(state.g.to_act, 1);
+
+        play(&mut state, PokerAction::Call);
+        assert_eq!(state.g.to_act, 2);
+        play(&mut state, PokerAction::Check);
+        assert_eq!(state.g.street, Street::Flop);
+        assert_eq!(state.g.to_act, 2);
+        play(&mut state, PokerAction::Check);
+        assert_eq!(state.g.to_act, 1);
+    }
+
+    #[test]
+    fn ring_action_starts_left_of_the_big_blind_then_left_of_the_button() {
+        let mut state = deal(table(4));
+        assert_eq!(state.g.to_act, 4);
+
+        play(&mut state, PokerAction::Fold);
+        play(&mut state, PokerAction::Call);
+        play(&mut state, PokerAction::Call);
+        assert_eq!(state.g.to_act, 3);
+        play(&mut state, PokerAction::Check);
+
+        // The folded seat is skipped
+        assert_eq!(state.g.street, Street::Flop);
+        assert_eq!(state.g.to_act, 2);
+        play(&mut state, PokerAction::Check);
+        assert_eq!(state.g.to_act, 3);
+        play(&mut state, PokerAction::Check);
+        assert_eq!(state.g.to_act, 1);
+    }
+
+    #[test]
+    fn forced_bets_are_posted_before_the_deal() {
+        let mut config = table(4);
+        config.forced_bets.ante = Chips(1);
+        let state = deal(config.clone());
+
+        // The blinds are posted left of the button, then every player antes
+        assert_eq!(state.g.events[1..], [
+            HandEvent::BlindPosted { player: 2, blind: Blind::Small, amount: Chips(1) },
+            HandEvent::BlindPosted { player: 3, blind: Blind::Big, amount: Chips(2) },
+            HandEvent::AntePosted { player: 1, amount: Chips(1) },
+            HandEvent::AntePosted { player: 2, amount: Chips(1) },
+            HandEvent::AntePosted { player: 3, amount: Chips(1) },
+            HandEvent::AntePosted { player: 4, amount: Chips(1) }
+        ]);
+        assert_eq!(state.g.chip_table, vec![Chips(0), Chips(1), Chips(2), Chips(0)]);
+        assert_eq!(state.g.hand_pot, Chips(4));
+        assert_eq!(state.g.bet_amount, Chips(2));
+        assert_eq!(state.g.to_act, 4);
+
+        config.forced_bets.ante = Chips(4);
+        config.forced_bets.big_blind_ante = true;
+        let state = deal(config);
+        assert_eq!(state.g.events[3..], [HandEvent::AntePosted { player: 3, amount: Chips(4) }]);
+        assert_eq!(state.g.chips, vec![Chips(64), Chips(63), Chips(58), Chips(64)]);
+    }
+
+    #[test]
+    fn the_smallest_raise_preflop_is_the_big_blind() {
+        let mut config = table(4);
+        config.bet_unit = Chips(10);
+        config.forced_bets.small_blind = Chips(5);
+        config.forced_bets.big_blind = Chips(10);
+        let state = deal(config);
+        assert_eq!(legal::legal_actions(&state.g, 4).min_raise_to, Some(Chips(20)));
+    }
+
+    #[test]
+    fn the_first_player_to_act_may_straddle() {
+        let mut config = table(4);
+        config.forced_bets.straddle = true;
+        let mut state = deal(config.clone());
+
+        assert!(legal::legal_actions(&state.g, 4).actions.contains(&PokerAction::Straddle));
+        play(&mut state, PokerAction::Straddle);
+        assert_eq!(state.g.events.last(), Some(&HandEvent::BlindPosted { player: 4, blind: Blind::Straddle, amount: Chips(4) }));
+        assert_eq!(state.g.bet_amount, Chips(4));
+        assert!(!legal::legal_actions(&state.g, 1).actions.contains(&PokerAction::Straddle));
+        assert_eq!(legal::legal_actions(&state.g, 1).min_raise_to, Some(Chips(8)));
+
+        // The straddle acts last preflop
+        for _ in 0..3 {
+            play(&mut state, PokerAction::Call);
+        }
+        assert_eq!(state.g.to_act, 4);
+        assert!(legal::legal_actions(&state.g, 4).can_check);
+
+        config.num_seats = 2;
+        assert!(!deal(config).g.can_straddle);
+    }
+
+    #[test]
+    fn last_contender_of_a_pot_has_to_show() {
+        // Seat 1 is all in for 10, seats 2 and 3 built a side pot of 80 and check the river down
+        let mut config = TableConfig::default();
+        config.num_seats = 3;
+        let river = Scenario {
+            config: config,
+            seats: vec![seat(0, 10, "Ah As"), seat(14, 50, "Kc Kd"), seat(14, 50, "2c 3d")],
+            dealer: 3,
+            street: Street::River,
+            board: cards("2h 7h 9c Qs 4s"),
+            pot: Chips(110),
+            to_act: 2
+        };
+        let mut state = start(river.build(&TableEntropy::from_seed(7)).unwrap());
+        play(&mut state, PokerAction::Check);
+        play(&mut state, PokerAction::Check);
+        play(&mut state, PokerAction::Muck);
+
+        assert_eq!(state.g.to_act, 3);
+        assert_eq!(legal::legal_actions(&state.g, 3).actions, vec![PokerAction::Show]);
+        assert_eq!(legal::validate_action(&state.g, 3, &PokerAction::Muck).unwrap_err().to_string(),
+            Errors::MustShow(3).to_string());
+
+        // Seat 1 only wins what it covered
+        play(&mut state, PokerAction::Show);
+        let awarded: Vec<HandEvent> = state.g.last_hand.unwrap().events.into_iter()
+            .filter(|event| match *event { HandEvent::PotAwarded { .. } => true, _ => false })
+            .collect();
+        assert_eq!(awarded, vec![
+            HandEvent::PotAwarded { pot: 0, player: 1, amount: Chips(30), ranking: Some(resolver::evaluate_best_hand(0, &cards("Ah As 2h 7h 9c Qs 4s"))) },
+            HandEvent::PotAwarded { pot: 1, player: 3, amount: Chips(80), ranking: Some(resolver::evaluate_best_hand(2, &cards("2c 3d 2h 7h 9c Qs 4s"))) }
+        ]);
+    }
+
+    #[test]
+    fn hands_are_only_dealt_from_the_table_entropy() {
+        let mut state = start(State { config: TableConfig::default(), ..State::default() });
+        seat_table(&mut state.g);
+        assert_eq!(begin_turn(&mut state).unwrap_err().to_string(), Errors::NoEntropy.to_string());
+
+        let entropy = TableEntropy::from_seed(7);
+        let mut state = start(new_table(TableConfig::default(), entropy.clone()));
+        begin_turn(&mut state).unwrap();
+        let seed = entropy.hand_seed(1);
+        let deck = state.g.config.shuffle.source().deck(seed);
+        assert_eq!(state.g.deck_commitment, Some(commitment::commit(seed, &deck)));
+    }
+
+    #[test]
+    fn finished_hands_reveal_their_seed() {
+        let entropy = TableEntropy::from_seed(7);
+        let mut state = start(new_table(TableConfig::default(), entropy.clone()));
+        play(&mut state, PokerAction::Fold);
+
+        let hand = state.g.last_hand.clone().unwrap();
+        assert_eq!(hand.seed, Some(commitment::seed_to_hex(entropy.hand_seed(1))));
+        assert!(hand.verify_deal().is_ok());
+
+        // The next deck is not the one after the revealed seed
+        let next_seed = entropy.hand_seed(1).wrapping_add(1);
+        let next_deck = state.g.config.shuffle.source().deck(next_seed);
+        assert_eq!(state.g.hand_number, 2);
+        assert!(state.g.deck_commitment.is_some());
+        assert_ne!(state.g.deck_commitment, Some(commitment::commit(next_seed, &next_deck)));
+        let seed = entropy.hand_seed(2);
+        assert_eq!(state.g.deck_commitment, Some(commitment::commit(seed, &state.g.config.shuffle.source().deck(seed))));
+    }
+
+    #[test]
+    fn lost_chips_are_caught() {
+        let mut state = State::default();
+        seat_table(&mut state);
+        assert!(check_chips_conserved(&state).is_ok());
+
+        state.chips[0] = Chips(63);
+        state.chip_table[0] = Chips(2);
+        assert_eq!(check_chips_conserved(&state).unwrap_err().to_string(),
+            Errors::ChipsNotConserved(Chips(128), Chips(129)).to_string());
+    }
 }
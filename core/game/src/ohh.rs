@@ -0,0 +1,549 @@
+/**
+ * Module that converts hand histories to and from the Open Hand History JSON format
+ */
+
+use std::error::Error;
+use betting::BettingStructure;
+use chips::Chips;
+use config::TableConfig;
+use events::{Blind, HandEvent};
+use history::HandHistory;
+use pots;
+use resolver;
+use {Card, Errors, Street};
+
+const SPEC_VERSION: &str = "1.4.6";
+const SITE_NAME: &str = "Oasis Poker";
+
+/// A hand in the Open Hand History format, the document is a single "ohh" object.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OpenHandHistory {
+    pub ohh: OhhHand
+}
+
+/// Amounts are in chips. Seats and player ids are both 1 to table_size.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OhhHand {
+    pub spec_version: String,
+    #[serde(default)]
+    pub site_name: String,
+    pub game_number: String,
+    pub start_date_utc: String,
+    #[serde(default)]
+    pub table_name: String,
+    pub game_type: String,
+    pub bet_limit: OhhBetLimit,
+    pub table_size: u16,
+    pub dealer_seat: u16,
+    pub small_blind_amount: Chips,
+    pub big_blind_amount: Chips,
+    #[serde(default)]
+    pub ante_amount: Chips,
+    pub players: Vec<OhhPlayer>,
+    pub rounds: Vec<OhhRound>,
+    pub pots: Vec<OhhPot>
+}
+
+/// NL, PL or FL. The cap is the number of bets allowed per round, zero for none.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OhhBetLimit {
+    pub bet_type: String,
+    #[serde(default)]
+    pub bet_cap: u8
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OhhPlayer {
+    pub id: u16,
+    pub seat: u16,
+    pub name: String,
+    pub starting_stack: Chips
+}
+
+/// A street and every action on it. Cards are the ones dealt to start the street.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OhhRound {
+    pub id: u16,
+    pub street: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cards: Vec<String>,
+    pub actions: Vec<OhhAction>
+}
+
+/// Unlike the moves, the amount is what the action added to the pot.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OhhAction {
+    pub action_number: u16,
+    pub player_id: u16,
+    pub action: String,
+    #[serde(default)]
+    pub amount: Chips,
+    #[serde(default)]
+    pub is_allin: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cards: Vec<String>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OhhPot {
+    pub number: u16,
+    pub amount: Chips,
+    #[serde(default)]
+    pub rake: Chips,
+    pub player_wins: Vec<OhhPlayerWin>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OhhPlayerWin {
+    pub player_id: u16,
+    pub win_amount: Chips
+}
+
+fn invalid(reason: String) -> Box<Error> {
+    Box::new(Errors::InvalidHandHistory(reason))
+}
+
+fn card_names(cards: &Vec<Card>) -> Vec<String> {
+    cards.iter().map(resolver::card_to_string).collect()
+}
+
+fn read_cards(names: &Vec<String>) -> Result<Vec<Card>, Box<Error>> {
+    names.iter()
+        .map(|name| resolver::card_from_string(name).ok_or(invalid(format!("{} is not a card", name))))
+        .collect()
+}
+
+fn street_name(street: Street) -> String {
+    format!("{:?}", street)
+}
+
+fn read_street(name: &str) -> Result<Street, Box<Error>> {
+    match name {
+        "Preflop" => Ok(Street::Preflop),
+        "Flop" => Ok(Street::Flop),
+        "Turn" => Ok(Street::Turn),
+        "River" => Ok(Street::River),
+        "Showdown" => Ok(Street::Showdown),
+        _ => Err(invalid(format!("{} is not a street", name)))
+    }
+}
+
+impl HandHistory {
+
+    /**
+     * Write the hand as Open Hand History. Like to_pokerstars, the caller passes
+     * the time the hand started, as an ISO 8601 UTC date like "2019-01-31T20:15:00Z".
+     */
+    pub fn to_ohh(&self, table_name: &str, start_date_utc: &str) -> OpenHandHistory {
+
+        let seats = self.stacks.len();
+        let forced_bets = &self.config.forced_bets;
+
+        let bet_limit = match self.config.betting_structure {
+            BettingStructure::NoLimit => OhhBetLimit { bet_type: String::from("NL"), bet_cap: 0 },
+            BettingStructure::PotLimit => OhhBetLimit { bet_type: String::from("PL"), bet_cap: 0 },
+            BettingStructure::FixedLimit { max_bets, .. } => OhhBetLimit { bet_type: String::from("FL"), bet_cap: max_bets }
+        };
+
+        let players = (0..seats).filter(|&idx| !self.stacks[idx].is_zero()).map(|idx| OhhPlayer {
+            id: idx as u16 + 1,
+            seat: idx as u16 + 1,
+            name: format!("Player {}", idx + 1),
+            starting_stack: self.stacks[idx]
+        }).collect();
+
+        let mut rounds = vec![OhhRound { id: 0, street: street_name(Street::Preflop), cards: Vec::new(), actions: Vec::new() }];
+        let mut pots: Vec<OhhPot> = Vec::new();
+        let mut on_table = vec![Chips::zero(); seats];
+        let mut action_number = 0;
+
+        for event in &self.events {
+
+            let (player, action, amount, is_allin, cards) = match *event {
+
+                HandEvent::BlindPosted { player, blind, amount } => {
+                    let idx = player as usize - 1;
+                    on_table[idx] = on_table[idx].saturating_add(amount);
+                    let action = match blind {
+                        Blind::Small => "Post SB",
+                        Blind::Big => "Post BB",
+                        Blind::Missed => "Post Extra Blind",
+                        Blind::Straddle => "Straddle"
+                    };
+                    (player, action, amount, false, Vec::new())
+                },
+
+                HandEvent::AntePosted { player, amount } => (player, "Post Ante", amount, false, Vec::new()),
+                HandEvent::Folded { player } => (player, "Fold", Chips::zero(), false, Vec::new()),
+                HandEvent::Checked { player } => (player, "Check", Chips::zero(), false, Vec::new()),
+
+                HandEvent::Called { player, to, all_in } | HandEvent::Bet { player, to, all_in } | HandEvent::Raised { player, to, all_in } => {
+                    let idx = player as usize - 1;
+                    let added = to.saturating_sub(on_table[idx]);
+                    on_table[idx] = to;
+                    let action = match *event {
+                        HandEvent::Called { .. } => "Call",
+                        HandEvent::Bet { .. } => "Bet",
+                        _ => "Raise"
+                    };
+                    (player, action, added, all_in, Vec::new())
+                },
+
+                HandEvent::Shown { player, ref cards } => {
+                    if rounds.last().map(|round| round.street.as_str()) != Some("Showdown") {
+                        rounds.push(OhhRound { id: rounds.len() as u16, street: street_name(Street::Showdown), cards: Vec::new(), actions: Vec::new() });
+                    }
+                    (player, "Shows Cards", Chips::zero(), false, card_names(cards))
+                },
+
+                HandEvent::Mucked { player } => (player, "Mucks Cards", Chips::zero(), false, Vec::new()),
+
+                HandEvent::StreetDealt { street, ref cards } => {
+                    on_table = vec![Chips::zero(); seats];
+                    rounds.push(OhhRound { id: rounds.len() as u16, street: street_name(street), cards: card_names(cards), actions: Vec::new() });
+                    continue;
+                },
+
+                HandEvent::PotAwarded { pot, player, amount, .. } => {
+                    // Pots are listed by number, whatever order they were awarded in
+                    let number = pot as u16;
+                    let idx = match pots.binary_search_by_key(&number, |ohh_pot| ohh_pot.number) {
+                        Ok(idx) => idx,
+                        Err(idx) => {
+                            pots.insert(idx, OhhPot { number: number, amount: Chips::zero(), rake: Chips::zero(), player_wins: Vec::new() });
+                            idx
+                        }
+                    };
+                    let ohh_pot = &mut pots[idx];
+                    ohh_pot.amount = ohh_pot.amount.saturating_add(amount);
+                    ohh_pot.player_wins.push(OhhPlayerWin { player_id: player, win_amount: amount });
+                    continue;
+                },
+
+                // The format has no action for it, the pots are only what was called
+                HandEvent::UncalledBetReturned { .. } | HandEvent::HandStarted { .. } | HandEvent::HandEnded => continue
+            };
+
+            action_number += 1;
+            rounds.last_mut().expect("There is always a preflop round").actions.push(OhhAction {
+                action_number: action_number,
+                player_id: player,
+                action: String::from(action),
+                amount: amount,
+                is_allin: is_allin,
+                cards: cards
+            });
+        }
+
+        OpenHandHistory {
+            ohh: OhhHand {
+                spec_version: String::from(SPEC_VERSION),
+                site_name: String::from(SITE_NAME),
+                game_number: self.hand_number.to_string(),
+                start_date_utc: String::from(start_date_utc),
+                table_name: String::from(table_name),
+                game_type: String::from("Holdem"),
+                bet_limit: bet_limit,
+                table_size: seats as u16,
+                dealer_seat: self.dealer,
+                small_blind_amount: forced_bets.small_blind,
+                big_blind_amount: forced_bets.big_blind,
+                ante_amount: forced_bets.ante,
+                players: players,
+                rounds: rounds,
+                pots: pots
+            }
+        }
+    }
+
+    /**
+     * Read a hand written as Open Hand History. The rankings of the pots won at the showdown
+     * are worked out again from the shown cards. The format does not hold the whole table
+     * config: stacks and bet sizes that are not in it are taken from the hand, and a
+     * fixed-limit game uses the big blind as its small bet.
+     */
+    pub fn from_ohh(document: &OpenHandHistory) -> Result<HandHistory, Box<Error>> {
+
+        let hand = &document.ohh;
+        let seats = hand.table_size as usize;
+        if hand.game_type != "Holdem" {
+            return Err(invalid(format!("{} is not hold'em", hand.game_type)));
+        }
+        if hand.dealer_seat == 0 || hand.dealer_seat as usize > seats {
+            return Err(invalid(format!("the button is on seat {} of {}", hand.dealer_seat, seats)));
+        }
+
+        let hand_number: u32 = hand.game_number.parse()
+            .map_err(|_| invalid(format!("{} is not a hand number", hand.game_number)))?;
+
+        // Players are looked up by id, their seat is their place at the table
+        let mut stacks = vec![Chips::zero(); seats];
+        let mut seat_of = Vec::new();
+        for player in &hand.players {
+            if player.seat == 0 || player.seat as usize > seats {
+                return Err(invalid(format!("seat {} is not at the table", player.seat)));
+            }
+            stacks[player.seat as usize - 1] = player.starting_stack;
+            seat_of.push((player.id, player.seat));
+        }
+        let seat = |player_id: u16| seat_of.iter()
+            .find(|&&(id, _)| id == player_id)
+            .map(|&(_, seat)| seat)
+            .ok_or(invalid(format!("player {} is not seated", player_id)));
+
+        let mut config = TableConfig::default();
+        config.num_seats = hand.table_size;
+        config.starting_chips = stacks.iter().cloned().max().unwrap_or(Chips::zero());
+        config.bet_unit = hand.big_blind_amount;
+        config.forced_bets.small_blind = hand.small_blind_amount;
+        config.forced_bets.big_blind = hand.big_blind_amount;
+        config.forced_bets.ante = hand.ante_amount;
+        config.betting_structure = match hand.bet_limit.bet_type.as_str() {
+            "NL" => BettingStructure::NoLimit,
+            "PL" => BettingStructure::PotLimit,
+            "FL" => BettingStructure::FixedLimit {
+                small_bet: hand.big_blind_amount,
+                big_bet: hand.big_blind_amount.saturating_mul(2),
+                max_bets: hand.bet_limit.bet_cap
+            },
+            bet_type => return Err(invalid(format!("{} is not a betting structure", bet_type)))
+        };
+
+        let mut events = vec![HandEvent::HandStarted { hand_number: hand_number, dealer: hand.dealer_seat, stacks: stacks.clone() }];
+        let mut hole_cards = vec![Vec::new(); seats];
+        let mut board = Vec::new();
+        let mut contributions = vec![Chips::zero(); seats];
+        let mut antes = 0;
+
+        for round in &hand.rounds {
+
+            let street = read_street(&round.street)?;
+            let mut on_table = vec![Chips::zero(); seats];
+
+            if street != Street::Preflop && street != Street::Showdown {
+                let cards = read_cards(&round.cards)?;
+                board.extend_from_slice(&cards);
+                events.push(HandEvent::StreetDealt { street: street, cards: cards });
+            }
+
+            for action in &round.actions {
+
+                let player = seat(action.player_id)?;
+                let idx = player as usize - 1;
+                let amount = action.amount;
+                let to = on_table[idx].saturating_add(amount);
+
+                let event = match action.action.as_str() {
+                    "Post SB" => HandEvent::BlindPosted { player: player, blind: Blind::Small, amount: amount },
+                    "Post BB" => HandEvent::BlindPosted { player: player, blind: Blind::Big, amount: amount },
+                    "Post Extra Blind" => HandEvent::BlindPosted { player: player, blind: Blind::Missed, amount: amount },
+                    "Straddle" => {
+                        config.forced_bets.straddle = true;
+                        HandEvent::BlindPosted { player: player, blind: Blind::Straddle, amount: amount }
+                    },
+                    "Post Ante" => {
+                        antes += 1;
+                        HandEvent::AntePosted { player: player, amount: amount }
+                    },
+                    "Fold" => HandEvent::Folded { player: player },
+                    "Check" => HandEvent::Checked { player: player },
+                    "Call" => HandEvent::Called { player: player, to: to, all_in: action.is_allin },
+                    "Bet" => HandEvent::Bet { player: player, to: to, all_in: action.is_allin },
+                    "Raise" => HandEvent::Raised { player: player, to: to, all_in: action.is_allin },
+                    "Shows Cards" => {
+                        let cards = read_cards(&action.cards)?;
+                        hole_cards[idx] = cards.clone();
+                        HandEvent::Shown { player: player, cards: cards }
+                    },
+                    "Mucks Cards" => HandEvent::Mucked { player: player },
+                    // Hole cards dealt to a hero are private, the history only keeps shown cards
+                    "Dealt Cards" => continue,
+                    other => return Err(invalid(format!("{} is not a supported action", other)))
+                };
+
+                // Antes go straight into the pot, everything else is on the table this round
+                match event {
+                    HandEvent::Folded { .. } | HandEvent::Checked { .. } | HandEvent::Shown { .. } | HandEvent::Mucked { .. } => (),
+                    HandEvent::AntePosted { .. } => contributions[idx] = contributions[idx].saturating_add(amount),
+                    _ => {
+                        on_table[idx] = to;
+                        contributions[idx] = contributions[idx].saturating_add(amount);
+                    }
+                }
+                events.push(event);
+            }
+
+            // A bet nobody called went back to its player when the round was over
+            if let Some((idx, amount)) = pots::uncalled_bet(&contributions) {
+                contributions[idx] = contributions[idx].saturating_sub(amount);
+                events.push(HandEvent::UncalledBetReturned { player: idx as u16 + 1, amount: amount });
+            }
+        }
+
+        // A single ante for a table of players is a big blind ante
+        let dealt_in = stacks.iter().filter(|stack| !stack.is_zero()).count();
+        config.forced_bets.big_blind_ante = antes == 1 && dealt_in > 1;
+
+        for pot in &hand.pots {
+            for win in &pot.player_wins {
+                let player = seat(win.player_id)?;
+                let idx = player as usize - 1;
+
+                // Only a hand shown with a full board can have won at the showdown
+                let ranking = match hole_cards[idx].len() == 2 && board.len() == 5 {
+                    true => {
+                        let mut working_hand = hole_cards[idx].clone();
+                        working_hand.extend_from_slice(&board);
+                        Some(resolver::evaluate_best_hand(idx, &working_hand))
+                    },
+                    false => None
+                };
+                events.push(HandEvent::PotAwarded { pot: pot.number as usize, player: player, amount: win.win_amount, ranking: ranking });
+            }
+        }
+        events.push(HandEvent::HandEnded);
+
+        Ok(HandHistory {
+            hand_number: hand_number,
+            config: config,
+            dealer: hand.dealer_seat,
+            stacks: stacks,
+            hole_cards: hole_cards,
+            board: board,
+            events: events,
+            commitment: None,
+            seed: None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    fn cards(names: &str) -> Vec<Card> {
+        names.split(' ').map(|name| resolver::card_from_string(name).unwrap()).collect()
+    }
+
+    // Three players with an ante: player 3 folds, player 2 shows a flush and wins against player 1's queens
+    fn history() -> HandHistory {
+        let mut config = TableConfig::default();
+        config.num_seats = 3;
+        config.forced_bets.ante = Chips(1);
+        let flush = resolver::evaluate_best_hand(1, &cards("Ah Kh 2h 7h 9c Qh 3s"));
+
+        HandHistory {
+            hand_number: 12,
+            config: config,
+            dealer: 1,
+            stacks: vec![Chips(64), Chips(64), Chips(64)],
+            hole_cards: vec![cards("Qs Qd"), cards("Ah Kh"), Vec::new()],
+            board: cards("2h 7h 9c Qh 3s"),
+            events: vec![
+                HandEvent::HandStarted { hand_number: 12, dealer: 1, stacks: vec![Chips(64), Chips(64), Chips(64)] },
+                HandEvent::BlindPosted { player: 2, blind: Blind::Small, amount: Chips(1) },
+                HandEvent::BlindPosted { player: 3, blind: Blind::Big, amount: Chips(2) },
+                HandEvent::AntePosted { player: 1, amount: Chips(1) },
+                HandEvent::AntePosted { player: 2, amount: Chips(1) },
+                HandEvent::AntePosted { player: 3, amount: Chips(1) },
+                HandEvent::Raised { player: 1, to: Chips(6), all_in: false },
+                HandEvent::Called { player: 2, to: Chips(6), all_in: false },
+                HandEvent::Folded { player: 3 },
+                HandEvent::StreetDealt { street: Street::Flop, cards: cards("2h 7h 9c") },
+                HandEvent::Checked { player: 2 },
+                HandEvent::Bet { player: 1, to: Chips(8), all_in: false },
+                HandEvent::Called { player: 2, to: Chips(8), all_in: false },
+                HandEvent::StreetDealt { street: Street::Turn, cards: cards("Qh") },
+                HandEvent::Checked { player: 2 },
+                HandEvent::Checked { player: 1 },
+                HandEvent::StreetDealt { street: Street::River, cards: cards("3s") },
+                HandEvent::Checked { player: 2 },
+                HandEvent::Checked { player: 1 },
+                HandEvent::Shown { player: 2, cards: cards("Ah Kh") },
+                HandEvent::Shown { player: 1, cards: cards("Qs Qd") },
+                HandEvent::PotAwarded { pot: 0, player: 2, amount: Chips(33), ranking: Some(flush) },
+                HandEvent::HandEnded
+            ],
+            commitment: None,
+            seed: None
+        }
+    }
+
+    #[test]
+    fn writes_open_hand_history() {
+        let document = serde_json::to_value(&history().to_ohh("Oasis 1", "2019-01-31T20:15:00Z")).unwrap();
+        let hand = &document["ohh"];
+
+        assert_eq!(hand["game_number"], json!("12"));
+        assert_eq!(hand["bet_limit"], json!({ "bet_type": "NL", "bet_cap": 0 }));
+        assert_eq!(hand["players"][2], json!({ "id": 3, "seat": 3, "name": "Player 3", "starting_stack": 64 }));
+        assert_eq!(hand["rounds"][0]["actions"][5],
+            json!({ "action_number": 6, "player_id": 1, "action": "Raise", "amount": 6, "is_allin": false }));
+        assert_eq!(hand["rounds"][1]["cards"], json!(["2h", "7h", "9c"]));
+        assert_eq!(hand["rounds"][1]["actions"][2]["amount"], json!(8));
+        assert_eq!(hand["rounds"][4]["street"], json!("Showdown"));
+        assert_eq!(hand["pots"], json!([{ "number": 0, "amount": 33, "rake": 0, "player_wins": [{ "player_id": 2, "win_amount": 33 }] }]));
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let hand = history();
+        let text = serde_json::to_string(&hand.to_ohh("Oasis 1", "2019-01-31T20:15:00Z")).unwrap();
+        let imported = HandHistory::from_ohh(&serde_json::from_str(&text).unwrap()).unwrap();
+
+        assert_eq!(imported, hand);
+        assert!(imported.check_result().is_ok());
+    }
+
+    #[test]
+    fn leaves_uncalled_bets_out_of_the_pots() {
+        // Player 1 raises and the blinds fold
+        let mut hand = history();
+        hand.hole_cards = vec![Vec::new(); 3];
+        hand.board = Vec::new();
+        hand.events.truncate(7);
+        hand.events.extend(vec![
+            HandEvent::Folded { player: 2 },
+            HandEvent::Folded { player: 3 },
+            HandEvent::UncalledBetReturned { player: 1, amount: Chips(4) },
+            HandEvent::PotAwarded { pot: 0, player: 1, amount: Chips(8), ranking: None },
+            HandEvent::HandEnded
+        ]);
+
+        let document = hand.to_ohh("Oasis 1", "2019-01-31T20:15:00Z");
+        assert_eq!(document.ohh.rounds[0].actions[5].amount, Chips(6));
+        assert_eq!(document.ohh.pots[0].amount, Chips(8));
+
+        let imported = HandHistory::from_ohh(&document).unwrap();
+        assert_eq!(imported, hand);
+        assert!(imported.check_result().is_ok());
+    }
+
+    #[test]
+    fn groups_awards_by_pot_number() {
+        // Side pot 1 is awarded before the main pot, and side pot 2 was not contested
+        let mut hand = history();
+        let end = hand.events.len() - 2;
+        hand.events.truncate(end);
+        hand.events.extend(vec![
+            HandEvent::PotAwarded { pot: 3, player: 1, amount: Chips(4), ranking: None },
+            HandEvent::PotAwarded { pot: 1, player: 2, amount: Chips(10), ranking: None },
+            HandEvent::PotAwarded { pot: 0, player: 1, amount: Chips(12), ranking: None },
+            HandEvent::PotAwarded { pot: 1, player: 1, amount: Chips(7), ranking: None },
+            HandEvent::HandEnded
+        ]);
+
+        let pots = hand.to_ohh("Oasis 1", "2019-01-31T20:15:00Z").ohh.pots;
+        let summary: Vec<(u16, Chips, usize)> = pots.iter().map(|pot| (pot.number, pot.amount, pot.player_wins.len())).collect();
+        assert_eq!(summary, vec![(0, Chips(12), 1), (1, Chips(17), 2), (3, Chips(4), 1)]);
+    }
+
+    #[test]
+    fn rejects_unknown_actions() {
+        let mut document = history().to_ohh("Oasis 1", "2019-01-31T20:15:00Z");
+        document.ohh.rounds[0].actions[6].action = String::from("Added Chips");
+        assert_eq!(HandHistory::from_ohh(&document).unwrap_err().to_string(),
+            Errors::InvalidHandHistory(String::from("Added Chips is not a supported action")).to_string());
+    }
+}
@@ -0,0 +1,157 @@
+/**
+ * Module that layers the chips put in during a hand into a main pot and side pots
+ */
+
+use chips::Chips;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Pot {
+    pub amount: Chips,
+    pub eligible: Vec<usize>
+}
+
+// Split the hand contributions into pots, starting with the main pot.
+// Each layer is capped by the smallest contribution of a player still in the hand,
+// so a player can only win what they covered. Folded chips stay in the pots,
+// but folded players are never eligible.
+pub fn build_pots(contributions: &Vec<Chips>, still_in: &Vec<bool>) -> Vec<Pot> {
+
+    let mut levels: Vec<Chips> = Vec::new();
+    for i in 0..contributions.len() {
+        if still_in[i] && !contributions[i].is_zero() && !levels.contains(&contributions[i]) {
+            levels.push(contributions[i]);
+        }
+    }
+    levels.sort();
+
+    let mut pots: Vec<Pot> = Vec::new();
+    let mut previous_level = Chips::zero();
+
+    for level in levels {
+
+        let mut amount = Chips::zero();
+        let mut eligible = Vec::new();
+
+        for i in 0..contributions.len() {
+
+            let contribution = contributions[i];
+            if contribution > previous_level {
+                amount = amount.saturating_add(contribution.min(level).saturating_sub(previous_level));
+            }
+
+            if still_in[i] && contribution >= level {
+                eligible.push(i);
+            }
+        }
+
+        pots.push(Pot {
+            amount: amount,
+            eligible: eligible
+        });
+        previous_level = level;
+    }
+
+    // Folded chips above every live player's contribution go to the last pot
+    let leftover: Chips = contributions.iter()
+        .filter(|contribution| **contribution > previous_level)
+        .map(|contribution| contribution.saturating_sub(previous_level))
+        .sum();
+
+    if !leftover.is_zero() {
+        match pots.last_mut() {
+            Some(pot) => pot.amount = pot.amount.saturating_add(leftover),
+            None => pots.push(Pot {
+                amount: leftover,
+                eligible: Vec::new()
+            })
+        }
+    }
+
+    pots
+}
+
+// The part of the biggest contribution nobody else matched, as (player, amount). Nobody
+// called it, so it goes back to the player who bet it instead of making a pot of its own.
+pub fn uncalled_bet(contributions: &Vec<Chips>) -> Option<(usize, Chips)> {
+
+    let top = (0..contributions.len()).max_by_key(|&i| contributions[i])?;
+    let called = (0..contributions.len()).filter(|&i| i != top).map(|i| contributions[i]).max().unwrap_or(Chips::zero());
+
+    match contributions[top].saturating_sub(called) {
+        uncalled if uncalled.is_zero() => None,
+        uncalled => Some((top, uncalled))
+    }
+}
+
+// Divide a pot evenly between tied winners. Odd chips go to the first
+// winner left of the button. Returns (player, amount) for every winner.
+pub fn split_pot(pot: &Pot, winners: &Vec<usize>, button: usize, num_seats: usize) -> Vec<(usize, Chips)> {
+
+    if winners.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ordered = winners.clone();
+    ordered.sort_by_key(|player| (player + num_seats - button - 1) % num_seats);
+
+    let (share, odd_chips) = pot.amount.split(ordered.len() as u64);
+
+    let mut awards: Vec<(usize, Chips)> = ordered.iter().map(|player| (*player, share)).collect();
+    awards[0].1 = share.saturating_add(odd_chips);
+
+    awards
+}
+
+pub fn pot_to_string(pot_idx: usize) -> String {
+    match pot_idx {
+        0 => String::from("main pot"),
+        _ => format!("side pot {}", pot_idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chips(amounts: &[u64]) -> Vec<Chips> {
+        amounts.iter().map(|amount| Chips(*amount)).collect()
+    }
+
+    #[test]
+    fn single_pot_when_everyone_covers() {
+        let pots = build_pots(&chips(&[10, 10, 10]), &vec![true, true, true]);
+        assert_eq!(pots, vec![Pot { amount: Chips(30), eligible: vec![0, 1, 2] }]);
+    }
+
+    #[test]
+    fn short_all_in_only_wins_what_they_covered() {
+        let pots = build_pots(&chips(&[5, 20, 20]), &vec![true, true, true]);
+        assert_eq!(pots, vec![
+            Pot { amount: Chips(15), eligible: vec![0, 1, 2] },
+            Pot { amount: Chips(30), eligible: vec![1, 2] }
+        ]);
+    }
+
+    #[test]
+    fn folded_chips_stay_in_the_pots() {
+        let pots = build_pots(&chips(&[4, 12, 30, 30]), &vec![true, false, true, true]);
+        assert_eq!(pots, vec![
+            Pot { amount: Chips(16), eligible: vec![0, 2, 3] },
+            Pot { amount: Chips(60), eligible: vec![2, 3] }
+        ]);
+    }
+
+    #[test]
+    fn odd_chip_goes_left_of_the_button() {
+        let pot = Pot { amount: Chips(11), eligible: vec![0, 1, 2] };
+        assert_eq!(split_pot(&pot, &vec![0, 2], 1, 3), vec![(2, Chips(6)), (0, Chips(5))]);
+        assert_eq!(split_pot(&pot, &vec![0, 2], 2, 3), vec![(0, Chips(6)), (2, Chips(5))]);
+    }
+
+    #[test]
+    fn only_the_chips_above_every_other_player_are_uncalled() {
+        assert_eq!(uncalled_bet(&chips(&[10, 25, 4])), Some((1, Chips(15))));
+        assert_eq!(uncalled_bet(&chips(&[10, 25, 30])), Some((2, Chips(5))));
+        assert_eq!(uncalled_bet(&chips(&[25, 25, 4])), None);
+    }
+}
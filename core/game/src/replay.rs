@@ -0,0 +1,151 @@
+/**
+ * Module that rebuilds a game from its entropy and the moves the players made
+ */
+
+use serde_json;
+use std::error::Error;
+use oasis_game_core::{Context, Move, UserState};
+use actions::{PokerAction, PokerMove};
+use config::TableConfig;
+use entropy::TableEntropy;
+use scenario::Scenario;
+use {Errors, State, begin_turn, current_players, finish_move, game_over, make_move, new_table};
+
+// The framework context a game starts with, before the first turn. The seed of every hand
+// comes from the table's entropy, so the context needs none.
+fn initial_context(num_players: u16) -> Context {
+    let players: Vec<u16> = (1..num_players + 1).collect();
+    Context::new(&players, None)
+}
+
+// A poker_move as the game server logs it
+pub fn poker_move(player_id: u16, action: PokerAction) -> Move {
+    Move {
+        move_type: String::from("poker_move"),
+        args: Some(json!([PokerMove::new(action).to_json()])),
+        player_id: player_id
+    }
+}
+
+/**
+ * Play a game again from the config and entropy it was created with and its move log,
+ * as returned by getMoves. Every move runs through the same turn, move and flow steps as
+ * on the game server, so the shuffles come out the same. A game created without players'
+ * entropy is replayed with TableEntropy::from_seed. Returns the state the game
+ * started in followed by the state after each move. A move that is rejected, or that
+ * comes after the game is over, fails the replay.
+ */
+pub fn replay(config: &TableConfig, entropy: &TableEntropy, moves: &Vec<Move>) -> Result<Vec<State>, Box<Error>> {
+    config.validate()?;
+    replay_from(new_table(config.clone(), entropy.clone()), moves)
+}
+
+/**
+ * Play a game that was started from a scenario again, like replay.
+ */
+pub fn replay_scenario(scenario: &Scenario, entropy: &TableEntropy, moves: &Vec<Move>) -> Result<Vec<State>, Box<Error>> {
+    replay_from(scenario.build(entropy)?, moves)
+}
+
+fn replay_from(initial_state: State, moves: &Vec<Move>) -> Result<Vec<State>, Box<Error>> {
+
+    let mut state = UserState {
+        ctx: initial_context(initial_state.config.num_seats),
+        g: initial_state
+    };
+    let mut states = vec![state.g.clone()];
+
+    for (i, game_move) in moves.iter().enumerate() {
+
+        let failed = |err: Box<Error>| Box::new(Errors::ReplayFailed(i + 1, err.to_string())) as Box<Error>;
+
+        state.ctx.action_players = current_players(&state);
+        begin_turn(&mut state).map_err(&failed)?;
+        if game_over(&state).is_some() {
+            return Err(Box::new(Errors::ReplayFailed(i + 1, String::from("the game is already over"))));
+        }
+
+        make_move(&mut state, game_move.player_id, &game_move.args).map_err(&failed)?;
+        finish_move(&mut state).map_err(&failed)?;
+        states.push(state.g.clone());
+    }
+
+    Ok(states)
+}
+
+/**
+ * Replay a game and check it ends in the state the caller holds, which must be the full
+ * state and not one player's view of it. Fails on the first difference.
+ */
+pub fn replay_to(config: &TableConfig, entropy: &TableEntropy, moves: &Vec<Move>, expected: &State) -> Result<Vec<State>, Box<Error>> {
+
+    let states = replay(config, entropy, moves)?;
+
+    let last = states.last().expect("A replay starts with the initial state");
+    if serde_json::to_value(last)? != serde_json::to_value(expected)? {
+        return Err(Box::new(Errors::ReplayMismatch(moves.len())));
+    }
+
+    Ok(states)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use legal;
+
+    // Every player calls or checks and shows down until the first hand is over
+    fn calling_station(config: &TableConfig, entropy: &TableEntropy) -> Vec<Move> {
+        let mut moves = Vec::new();
+        loop {
+            // The first hand is dealt when the first turn begins
+            let mut state = UserState {
+                g: replay(config, entropy, &moves).unwrap().pop().unwrap(),
+                ctx: initial_context(config.num_seats)
+            };
+            begin_turn(&mut state).unwrap();
+            if state.g.hand_number > 1 {
+                return moves;
+            }
+
+            let player_id = state.g.to_act;
+            let legal = legal::legal_actions(&state.g, player_id).actions;
+            let action = [PokerAction::Check, PokerAction::Call, PokerAction::Show].iter()
+                .find(|action| legal.contains(action))
+                .cloned()
+                .unwrap();
+            moves.push(poker_move(player_id, action));
+        }
+    }
+
+    #[test]
+    fn replays_the_same_game() {
+        let config = TableConfig::default();
+        let entropy = TableEntropy::from_seed(7);
+        let moves = calling_station(&config, &entropy);
+
+        let states = replay(&config, &entropy, &moves).unwrap();
+        assert_eq!(states.len(), moves.len() + 1);
+        assert!(states.last().unwrap().last_hand.is_some());
+        assert!(replay_to(&config, &entropy, &moves, states.last().unwrap()).is_ok());
+
+        let other_seed = replay(&config, &TableEntropy::from_seed(8), &moves).map(|states| states.last().unwrap().last_hand.clone());
+        assert!(other_seed.is_err() || other_seed.unwrap() != states.last().unwrap().last_hand);
+    }
+
+    #[test]
+    fn fails_loudly() {
+        let config = TableConfig::default();
+        let entropy = TableEntropy::from_seed(7);
+        let mut moves = calling_station(&config, &entropy);
+        let mut last = replay(&config, &entropy, &moves).unwrap().pop().unwrap();
+
+        last.chips[0] = last.chips[0].saturating_add(::Chips(1));
+        assert_eq!(replay_to(&config, &entropy, &moves, &last).unwrap_err().to_string(),
+            Errors::ReplayMismatch(moves.len()).to_string());
+
+        moves[0].player_id = 2;
+        assert_eq!(replay(&config, &entropy, &moves).unwrap_err().to_string(),
+            Errors::ReplayFailed(1, Errors::NotYourTurn(2).to_string()).to_string());
+    }
+}
@@ -16,6 +16,9 @@ const TWO_PAIR : u8 = 2;
 const PAIR : u8 = 1;
 const HIGH : u8 = 0;
 
+const RANK_CHARS: &str = "23456789TJQKA";
+const SUIT_CHARS: &str = "dchs";
+
 const HAND_TO_STRING: [(u8, &str); 9] =
     [(HIGH, "High Card"),
      (PAIR, "Pair"),
@@ -32,6 +35,24 @@ pub fn hand_to_string(hand: &u8) -> String {
     return String::from(*hand_to_string_map.get(hand).expect(""));
 }
 
+// Short name of a card, like "Ah" or "Td"
+pub fn card_to_string(card: &Card) -> String {
+    let rank = RANK_CHARS.chars().nth(card.rank as usize).unwrap_or('?');
+    let suit = SUIT_CHARS.chars().nth(card.suit as usize).unwrap_or('?');
+    format!("{}{}", rank, suit)
+}
+
+// Read a card from its short name, None if it is not a card
+pub fn card_from_string(name: &str) -> Option<Card> {
+    let mut chars = name.chars();
+    let rank = chars.next().and_then(|rank| RANK_CHARS.find(rank))?;
+    let suit = chars.next().and_then(|suit| SUIT_CHARS.find(suit))?;
+    match chars.next() {
+        Some(_) => None,
+        None => Some(Card { suit: suit as u8, rank: rank as u8 })
+    }
+}
+
 // Given a list of 7 card combinations, return the best hand
 // Leave an arbitrary Vec in the return value for tiebreaking
 pub fn evaluate_best_hand(player: usize, hand: &Vec<Card>) -> CardRanking {
@@ -68,12 +89,14 @@ pub fn evaluate_best_hand(player: usize, hand: &Vec<Card>) -> CardRanking {
 
     let histogram = to_histogram(&working_hand);
 
-    // Quads
+    // Quads, the kicker is the best other card whether or not it is part of a pair
     if histogram.contains_key(&4) {
+        let quad = *histogram.get(&4).expect("");
+        let kicker = working_hand.iter().find(|card| card.rank != quad).map(|card| card.rank).expect("");
         return CardRanking {
             player: player,
             hand: QUADS, 
-            tiebreak: vec![*histogram.get(&4).expect(""), *histogram.get(&1).expect("")]
+            tiebreak: vec![quad, kicker]
             };
     }
 
@@ -372,4 +395,38 @@ fn to_ranked_subsets(hand_of_seven: &Vec<Card>) -> Vec<Vec<Card>> {
         }
     }
     ranked_subsets
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(ranks: &[u8]) -> Vec<Card> {
+        ranks.iter().enumerate().map(|(i, rank)| Card { suit: (i % 4) as u8, rank: *rank }).collect()
+    }
+
+    #[test]
+    fn quads_kicker_can_come_from_a_pair_or_trips() {
+        let with_trips = evaluate_best_hand(0, &cards(&[9, 9, 9, 9, 4, 4, 4]));
+        let with_pair = evaluate_best_hand(1, &cards(&[9, 9, 9, 9, 4, 4, 2]));
+        assert_eq!(with_trips.hand, QUADS);
+        assert_eq!(with_trips.tiebreak, vec![9, 4]);
+        assert_eq!(with_pair.tiebreak, vec![9, 4]);
+    }
+
+    #[test]
+    fn quads_with_two_pairs_on_the_board_keep_the_higher_pair() {
+        // Pocket nines on 9 9 K K 4, the kings play over the single four
+        let quads = evaluate_best_hand(0, &cards(&[9, 9, 9, 9, 11, 11, 4]));
+        assert_eq!(quads.hand, QUADS);
+        assert_eq!(quads.tiebreak, vec![9, 11]);
+    }
+
+    #[test]
+    fn card_names() {
+        assert_eq!(card_to_string(&Card { suit: 2, rank: 12 }), "Ah");
+        assert_eq!(card_to_string(&Card { suit: 0, rank: 8 }), "Td");
+        assert_eq!(card_from_string("Td"), Some(Card { suit: 0, rank: 8 }));
+        assert_eq!(card_from_string("1x"), None);
+    }
+}
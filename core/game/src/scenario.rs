@@ -0,0 +1,397 @@
+/**
+ * Module that sets up a hand from an arbitrary position, for tests and teaching
+ */
+
+use std::error::Error;
+use std::sync::Mutex;
+use chips::Chips;
+use config::TableConfig;
+use deck::{NUM_DECK_SUITS, NUM_DECK_VALUES};
+use entropy::TableEntropy;
+use events::{self, HandEvent};
+use resolver;
+use {Card, Errors, State, Street, street_bet_unit};
+
+/// One seat in a scenario. The stack is what the player has behind, the bet is what they
+/// have on the table this street and invested is what they put in on earlier streets,
+/// antes included. Players in the hand hold two cards, a folded player may hold none.
+/// A seat with no chips anywhere and no cards is sitting out.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(default)]
+pub struct Seat {
+    pub stack: Chips,
+    pub bet: Chips,
+    pub invested: Chips,
+    pub hole_cards: Vec<Card>,
+    pub folded: bool
+}
+
+/// A position in the middle of a hand: the seats, the button, the street, the board,
+/// the pot from earlier streets and the player to act. Seats and the button are 1 to
+/// num_seats. The blinds are placed from the button as usual.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Scenario {
+    pub config: TableConfig,
+    pub seats: Vec<Seat>,
+    pub dealer: u16,
+    pub street: Street,
+    pub board: Vec<Card>,
+    pub pot: Chips,
+    pub to_act: u16
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Scenario {
+            config: TableConfig::default(),
+            seats: Vec::new(),
+            dealer: 1,
+            street: Street::Preflop,
+            board: Vec::new(),
+            pot: Chips::zero(),
+            to_act: 1
+        }
+    }
+}
+
+fn invalid(reason: String) -> Box<Error> {
+    Box::new(Errors::InvalidScenario(reason))
+}
+
+impl Seat {
+
+    fn is_dealt_in(&self) -> bool {
+        !self.hole_cards.is_empty() || !self.stack.is_zero() || !self.bet.is_zero() || !self.invested.is_zero()
+    }
+
+    fn is_in_hand(&self) -> bool {
+        self.is_dealt_in() && !self.folded
+    }
+}
+
+impl Scenario {
+
+    /**
+     * Check the position could come up in a real hand: every card is dealt once, the board
+     * fits the street, the pot holds what was invested and the player to act can act.
+     */
+    pub fn validate(&self) -> Result<(), Box<Error>> {
+
+        self.config.validate_rules()?;
+
+        let num_seats = self.config.num_seats as usize;
+        if self.seats.len() != num_seats {
+            return Err(invalid(format!("the table has {} seats but {} are described", num_seats, self.seats.len())));
+        }
+        if self.dealer == 0 || self.dealer as usize > num_seats {
+            return Err(invalid(format!("there is no seat {} for the button", self.dealer)));
+        }
+        if !self.street.is_betting() {
+            return Err(invalid(format!("the {:?} is not a betting street", self.street)));
+        }
+
+        let board_size = match self.street {
+            Street::Flop => 3,
+            Street::Turn => 4,
+            Street::River => 5,
+            _ => 0
+        };
+        if self.board.len() != board_size {
+            return Err(invalid(format!("the {:?} has {} cards on the board, found {}", self.street, board_size, self.board.len())));
+        }
+
+        let mut dealt: Vec<&Card> = Vec::new();
+        for card in self.seats.iter().flat_map(|seat| seat.hole_cards.iter()).chain(self.board.iter()) {
+            if card.suit >= NUM_DECK_SUITS || card.rank >= NUM_DECK_VALUES {
+                return Err(invalid(format!("{:?} is not a card", card)));
+            }
+            if dealt.contains(&card) {
+                return Err(invalid(format!("{} is dealt twice", resolver::card_to_string(card))));
+            }
+            dealt.push(card);
+        }
+
+        for (i, seat) in self.seats.iter().enumerate() {
+            let holds = seat.hole_cards.len();
+            if holds != 2 && (seat.is_in_hand() || holds != 0) {
+                return Err(invalid(format!("seat {} holds {} cards", i + 1, holds)));
+            }
+        }
+
+        if self.seats.iter().filter(|seat| seat.is_in_hand()).count() < 2 {
+            return Err(invalid(String::from("fewer than two players are in the hand")));
+        }
+
+        // The chips on the table add up: the pot is what every seat invested before this street
+        let invested = Chips::checked_sum(self.seats.iter().map(|seat| &seat.invested))
+            .ok_or(Box::new(Errors::ChipOverflow))?;
+        if invested != self.pot {
+            return Err(invalid(format!("the seats invested {} but the pot holds {}", invested, self.pot)));
+        }
+        let counted = self.seats.iter().flat_map(|seat| vec![&seat.stack, &seat.bet]).chain(Some(&self.pot));
+        Chips::checked_sum(counted).ok_or(Box::new(Errors::ChipOverflow))?;
+
+        let to_act = match self.to_act {
+            to_act if to_act != 0 && to_act as usize <= num_seats => &self.seats[to_act as usize - 1],
+            to_act => return Err(invalid(format!("there is no seat {} to act", to_act)))
+        };
+        if !to_act.is_in_hand() || to_act.stack.is_zero() {
+            return Err(invalid(format!("seat {} cannot act", self.to_act)));
+        }
+
+        Ok(())
+    }
+
+    // Seats in the hand with chips behind, in the order they act on this street
+    fn order(&self) -> Vec<usize> {
+
+        let num_seats = self.seats.len();
+        let (_, big_blind) = self.blind_seats();
+        let after = match self.street {
+            Street::Preflop => big_blind,
+            _ => self.dealer as usize - 1
+        };
+
+        (1..num_seats + 1)
+            .map(|offset| (after + offset) % num_seats)
+            .filter(|&idx| self.seats[idx].is_in_hand() && !self.seats[idx].stack.is_zero())
+            .collect()
+    }
+
+    // The small blind and big blind seats for this button, heads-up the button posts the small blind
+    fn blind_seats(&self) -> (usize, usize) {
+
+        let num_seats = self.seats.len();
+        let dealer_idx = self.dealer as usize - 1;
+        let next_dealt_in = |idx: usize| (1..num_seats + 1)
+            .map(|offset| (idx + offset) % num_seats)
+            .find(|&seat| self.seats[seat].is_dealt_in())
+            .unwrap_or(idx);
+
+        let small_blind = match self.seats.iter().filter(|seat| seat.is_dealt_in()).count() {
+            2 => dealer_idx,
+            _ => next_dealt_in(dealer_idx)
+        };
+
+        (small_blind, next_dealt_in(small_blind))
+    }
+
+    /**
+     * The state of the game at this position, ready for the flow to play on from. The rest of
+     * the deck is shuffled with the seed of the first hand of the table's entropy. The raises
+     * this street are counted from the different bets on the table, so a fixed-limit cap only
+     * sees the raises that are still showing.
+     */
+    pub fn build(&self, entropy: &TableEntropy) -> Result<State, Box<Error>> {
+
+        self.validate()?;
+
+        let num_seats = self.seats.len();
+        let (small_blind, big_blind) = self.blind_seats();
+        let mut state = State::default();
+        state.config = self.config.clone();
+        state.entropy = Some(entropy.clone());
+        state.street = self.street;
+        state.hand_number = 1;
+        state.dealer = self.dealer;
+        state.small_blind_seat = small_blind as u16 + 1;
+        state.big_blind_seat = big_blind as u16 + 1;
+        state.to_act = self.to_act;
+
+        state.hands = self.seats.iter().map(|seat| seat.hole_cards.clone()).collect();
+        state.still_in = self.seats.iter().map(|seat| seat.is_in_hand()).collect();
+        state.chips = self.seats.iter().map(|seat| seat.stack).collect();
+        state.chip_table = self.seats.iter().map(|seat| seat.bet).collect();
+        state.hand_contributions = self.seats.iter().map(|seat| seat.invested.saturating_add(seat.bet)).collect();
+        state.card_table = self.board.clone();
+        state.hand_pot = self.pot;
+        state.chips_in_play = state.chips.iter().chain(state.chip_table.iter()).chain(Some(&self.pot)).sum();
+        state.missed_big_blind = vec![false; num_seats];
+        state.shown = vec![false; num_seats];
+
+        // Raises are sized from the bets already on the table this street
+        let opening_level = match self.street {
+            Street::Preflop => self.seats.iter().map(|seat| seat.bet).max().unwrap_or(Chips::zero()).min(self.config.forced_bets.big_blind),
+            _ => Chips::zero()
+        };
+        let mut levels: Vec<Chips> = self.seats.iter().map(|seat| seat.bet).filter(|bet| *bet > opening_level).collect();
+        levels.push(opening_level);
+        levels.sort();
+        levels.dedup();
+        state.bet_amount = levels[levels.len() - 1];
+        let last_raise = match levels.len() {
+            1 => Chips::zero(),
+            n => levels[n - 1].saturating_sub(levels[n - 2])
+        };
+        state.bets_this_street = match self.street {
+            Street::Preflop => levels.len() as u8,
+            _ => levels.len() as u8 - 1
+        };
+        state.last_raise = last_raise.max(street_bet_unit(&state));
+        state.last_aggressor = match state.bet_amount > opening_level {
+            true => self.seats.iter().position(|seat| seat.bet == state.bet_amount).map(|idx| idx as u16 + 1).unwrap_or(0),
+            false => 0
+        };
+
+        // Once there is a bet, whoever matched it has acted. Before that, everyone from the player to act on still has to.
+        let order = self.order();
+        let to_act_at = order.iter().position(|&idx| idx == self.to_act as usize - 1).unwrap_or(0);
+        let opened = state.bet_amount > opening_level;
+        state.needs_action = vec![false; num_seats];
+        state.can_raise = vec![false; num_seats];
+        for (position, &idx) in order.iter().enumerate() {
+            state.needs_action[idx] = self.seats[idx].bet < state.bet_amount || (!opened && position >= to_act_at);
+            state.can_raise[idx] = true;
+        }
+        if !state.needs_action[self.to_act as usize - 1] {
+            return Err(invalid(format!("seat {} has already matched the bet", self.to_act)));
+        }
+
+        // Before anyone acts preflop, the first player may straddle like in a dealt hand
+        state.can_straddle = self.config.forced_bets.straddle && self.street == Street::Preflop && !opened && to_act_at == 0
+            && self.seats.iter().filter(|seat| !seat.stack.is_zero()).count() > 2;
+
+        // Every card in play comes out of the deck before the rest of the hand is dealt.
+        // The cards in play were not dealt from it, so the hand carries no commitment.
+        let mut deck = self.config.shuffle.source().deck(entropy.hand_seed(1));
+        let in_play: Vec<Card> = state.hands.iter().flat_map(|hand| hand.iter().cloned()).chain(self.board.iter().cloned()).collect();
+        deck.remove(&in_play);
+        state.deck = Some(deck);
+
+        // The history of the hand starts at this position
+        let stacks = self.seats.iter().map(|seat| seat.stack.saturating_add(seat.bet).saturating_add(seat.invested)).collect();
+        state.events.push(HandEvent::HandStarted { hand_number: 1, dealer: self.dealer, stacks: stacks });
+        let mut board_dealt = 0;
+        let mut street = Street::Flop;
+        while board_dealt < self.board.len() {
+            let cards = self.board[board_dealt..board_dealt + street.cards_dealt()].to_vec();
+            board_dealt += cards.len();
+            state.events.push(HandEvent::StreetDealt { street: street, cards: cards });
+            street = street.next();
+        }
+        state.last_move = events::describe_last_move(&state.events);
+
+        Ok(state)
+    }
+}
+
+lazy_static! {
+    static ref SCENARIO: Mutex<Option<Scenario>> = Mutex::new(None);
+}
+
+/**
+ * Start the next game created from this position instead of a new table, or from a new
+ * table again with None. Flow::initial_state reads it, like the table config.
+ */
+pub fn set_scenario(scenario: Option<Scenario>) -> Result<(), Box<Error>> {
+    if let Some(ref scenario) = scenario {
+        scenario.validate()?;
+    }
+    *SCENARIO.lock().expect("Scenario lock poisoned") = scenario;
+    Ok(())
+}
+
+pub fn scenario() -> Option<Scenario> {
+    SCENARIO.lock().expect("Scenario lock poisoned").clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oasis_game_core::Move;
+    use actions::{PokerAction, PokerMove};
+    use replay;
+
+    fn cards(names: &str) -> Vec<Card> {
+        names.split(' ').map(|name| resolver::card_from_string(name).unwrap()).collect()
+    }
+
+    fn seat(stack: u64, bet: u64, invested: u64, hole_cards: &str) -> Seat {
+        Seat { stack: Chips(stack), bet: Chips(bet), invested: Chips(invested), hole_cards: cards(hole_cards), folded: false }
+    }
+
+    // Three handed on the turn: seat 3 bet 10 into 24, seat 1 is to act, seat 2 already folded
+    fn turn_spot() -> Scenario {
+        let mut config = TableConfig::default();
+        config.num_seats = 3;
+        let mut folded = seat(56, 0, 8, "2c 3c");
+        folded.folded = true;
+
+        Scenario {
+            config: config,
+            seats: vec![seat(48, 0, 8, "Ah Kh"), folded, seat(38, 10, 8, "Qs Qd")],
+            dealer: 2,
+            street: Street::Turn,
+            board: cards("2h 7h 9c Qh"),
+            pot: Chips(24),
+            to_act: 1
+        }
+    }
+
+    #[test]
+    fn builds_the_position() {
+        let state = turn_spot().build(&TableEntropy::from_seed(3)).unwrap();
+        assert_eq!(state.bet_amount, Chips(10));
+        assert_eq!(state.needs_action, vec![true, false, false]);
+        assert_eq!(state.still_in, vec![true, false, true]);
+        assert_eq!(state.hand_contributions, vec![Chips(8), Chips(8), Chips(18)]);
+        assert_eq!(state.chips_in_play, Chips(176));
+        assert_eq!(state.last_aggressor, 3);
+        assert_eq!(state.deck.unwrap().remaining(), 52 - 10);
+    }
+
+    #[test]
+    fn plays_on_from_the_position() {
+        let moves = vec![Move {
+            move_type: String::from("poker_move"),
+            args: Some(json!([PokerMove::new(PokerAction::Call).to_json()])),
+            player_id: 1
+        }];
+        let state = replay::replay_scenario(&turn_spot(), &TableEntropy::from_seed(3), &moves).unwrap().pop().unwrap();
+
+        assert_eq!(state.street, Street::River);
+        assert_eq!(state.hand_pot, Chips(44));
+        assert_eq!(state.card_table.len(), 5);
+        assert!(!turn_spot().seats.iter().any(|seat| seat.hole_cards.contains(&state.card_table[4])));
+        assert_eq!(state.to_act, 3);
+    }
+
+    #[test]
+    fn the_first_player_preflop_may_straddle() {
+        let mut config = TableConfig::default();
+        config.num_seats = 3;
+        config.forced_bets.straddle = true;
+        let mut preflop = Scenario {
+            config: config,
+            seats: vec![seat(64, 0, 0, "Ah Kh"), seat(63, 1, 0, "2c 3c"), seat(62, 2, 0, "Qs Qd")],
+            to_act: 1,
+            ..Scenario::default()
+        };
+        assert!(preflop.build(&TableEntropy::from_seed(3)).unwrap().can_straddle);
+
+        preflop.config.forced_bets.straddle = false;
+        assert!(!preflop.build(&TableEntropy::from_seed(3)).unwrap().can_straddle);
+    }
+
+    #[test]
+    fn rejects_impossible_positions() {
+        let rejected = |scenario: Scenario| scenario.validate().unwrap_err().to_string();
+
+        let mut twice = turn_spot();
+        twice.board[3] = cards("Ah").remove(0);
+        assert_eq!(rejected(twice), Errors::InvalidScenario(String::from("Ah is dealt twice")).to_string());
+
+        let mut short_pot = turn_spot();
+        short_pot.pot = Chips(20);
+        assert_eq!(rejected(short_pot), Errors::InvalidScenario(String::from("the seats invested 24 but the pot holds 20")).to_string());
+
+        let mut no_river = turn_spot();
+        no_river.street = Street::River;
+        assert!(no_river.validate().is_err());
+
+        let mut folded_to_act = turn_spot();
+        folded_to_act.to_act = 2;
+        assert_eq!(rejected(folded_to_act), Errors::InvalidScenario(String::from("seat 2 cannot act")).to_string());
+    }
+}
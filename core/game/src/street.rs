@@ -0,0 +1,67 @@
+/**
+ * Module that defines the stages of a hand
+ */
+
+/// Where a hand is. Betting happens preflop, on the flop, turn and river.
+/// Showdown decides the pots, and a complete hand is followed by the next deal
+/// without waiting for any player.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Street {
+    Preflop,
+    Flop,
+    Turn,
+    River,
+    Showdown,
+    HandComplete
+}
+
+impl Street {
+
+    // The street that follows this one when the hand plays on
+    pub fn next(self) -> Street {
+        match self {
+            Street::Preflop => Street::Flop,
+            Street::Flop => Street::Turn,
+            Street::Turn => Street::River,
+            Street::River => Street::Showdown,
+            Street::Showdown => Street::HandComplete,
+            Street::HandComplete => Street::Preflop
+        }
+    }
+
+    pub fn is_betting(self) -> bool {
+        match self {
+            Street::Preflop | Street::Flop | Street::Turn | Street::River => true,
+            Street::Showdown | Street::HandComplete => false
+        }
+    }
+
+    // Community cards dealt when the hand moves on to this street
+    pub fn cards_dealt(self) -> usize {
+        match self {
+            Street::Flop => 3,
+            Street::Turn | Street::River => 1,
+            _ => 0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_hand_deals_five_community_cards() {
+        let mut street = Street::Preflop;
+        let mut board = 0;
+
+        while street.is_betting() {
+            street = street.next();
+            board += street.cards_dealt();
+        }
+
+        assert_eq!(street, Street::Showdown);
+        assert_eq!(board, 5);
+        assert_eq!(Street::HandComplete.next(), Street::Preflop);
+    }
+}
@@ -1,3 +1,4 @@
+const fs = require('fs')
 const Web3 = require('web3')
 const Web3c = require('web3c')
 const chalk = require('chalk')
@@ -8,7 +9,7 @@ const { Game, GameServer } = require('oasis-game-client')
 const truffleConfig = require('../truffle-config.js')
 
 let args = minimist(process.argv.slice(2), {
-  string: ['bots', 'players'],
+  string: ['bots', 'players', 'config'],
   boolean: ['confidential'],
   default: {
     confidential: true
@@ -38,6 +39,10 @@ module.exports = async function (cb) {
     }
   })]
 
+  // The table rules come from a JSON file, with one seat for every player unless it says otherwise
+  let tableConfig = Object.assign({ num_seats: playerArgs.length },
+    args.config ? JSON.parse(fs.readFileSync(args.config, 'utf8')) : {})
+
   let spinner = ora({
     text: chalk.blue(`Creating a new game with game contract ${serverAddress}`),
     color: 'blue'
@@ -51,7 +56,7 @@ module.exports = async function (cb) {
   })
 
   try {
-    let game = await server.createGame(playerArgs)
+    let game = await server.createGame(playerArgs, Buffer.from(JSON.stringify(tableConfig)))
     await game.ready()
     spinner.succeed(chalk.green(`Created a new game with ID: ${game.id}`))
   } catch (err) {
@@ -3,75 +3,45 @@ import PropTypes from 'prop-types';
 import Button from './Button';
 import './ActionList.css'
 
-const ACTION = {
-  FOLD: 0,
-  CHECK_OR_CALL: 1,
-  BET_OR_RAISE: 2,
-  ALL_IN: 3,
-  CONFIRM: 99
-};
+const PROTOCOL_VERSION = 1;
+
+// Button label for each legal action, actions without a label are not offered
+function actionName (action, legal) {
+  switch (action.type) {
+    case 'Fold': return 'Fold';
+    case 'Show': return 'Show';
+    case 'Muck': return 'Muck';
+    case 'Check': return 'Check';
+    case 'Call': return `Call ${legal.to_call}`;
+    case 'Bet': return `Bet ${action.to}`;
+    case 'Raise': return `Raise ${action.to}`;
+    case 'AllIn': return 'All in';
+    case 'Straddle': return 'Straddle';
+    case 'BetPreset':
+      if (action.preset === 'HalfPot') return 'Half pot';
+      if (action.preset === 'Pot') return 'Pot';
+      return null;
+    default: return null;
+  }
+}
 
-const ActionList = function ({ lastMove, onClick }) {
+const ActionList = function ({ legal, onClick }) {
   let actions = [];
 
-  if (lastMove.indexOf('HAND OVER') !== -1) {
-    actions.push({
-      name: 'Confirm',
-      clickId: ACTION.CONFIRM
-    })
-  } else {  
-    // Only fold if responding to a bet, raise or all in
-    let responding = (lastMove.indexOf('Bet') !== -1 || lastMove.indexOf('Raise') !== -1 || lastMove.indexOf('All') !== -1);
-
-    if (responding) {
-      actions.push({
-        name: 'Fold',
-        clickId: ACTION.FOLD
-      })
-    }
-
-    // CHECK or CALL
-    if (responding) {
-      actions.push({
-        name: 'Call',
-        clickId: ACTION.CHECK_OR_CALL
-      })
-    } else {
-      actions.push({
-        name: 'Check',
-        clickId: ACTION.CHECK_OR_CALL
-      })
-    }
-      
-    // If all in, no more additional bet, raise or all in.
-    if (lastMove.indexOf('All') == -1) {
-
-      // BET or RAISE
-      if (responding) {
-        actions.push({
-          name: 'Raise',
-          clickId: ACTION.BET_OR_RAISE
-        })
-      } else {
-        actions.push({
-          name: 'Bet',
-          clickId: ACTION.BET_OR_RAISE
-        })
+  if (legal) {
+    legal.actions.forEach(action => {
+      let name = actionName(action, legal);
+      if (name) {
+        actions.push({ name, action });
       }
-
-      // All in, exists regardless of responding or not
-      actions.push({
-        name: 'All in',
-        clickId: ACTION.ALL_IN
-      });
-    }
+    });
   }
 
   return (
     <div className="ActionList">
-      {actions.map(action =>
-        <div key={action.name} className="ActionList__item">
-          <Button text={action.name} onClick={() => onClick(action.clickId)} />
+      {actions.map(({ name, action }) =>
+        <div key={name} className="ActionList__item">
+          <Button text={name} onClick={() => onClick({ version: PROTOCOL_VERSION, action })} />
         </div>
       )}
     </div>
@@ -79,7 +49,7 @@ const ActionList = function ({ lastMove, onClick }) {
 }
 
 ActionList.propTypes = {
-  lastMove: PropTypes.string.isRequired,
+  legal: PropTypes.object,
   onClick: PropTypes.func.isRequired
 }
 
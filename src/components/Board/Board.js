@@ -31,13 +31,20 @@ class Board extends React.Component {
     moves: PropTypes.any.isRequired,
     playerID: PropTypes.number,
     isSpectating: PropTypes.bool,
-    isMultiplayer: PropTypes.bool
+    isMultiplayer: PropTypes.bool,
+    bindings: PropTypes.any
   };
 
-  onClick = id => {
-    this.props.moves.poker_move(id)
+  onClick = pokerMove => {
+    this.props.moves.poker_move(pokerMove)
   };
 
+  getLegalActions() {
+    // The wasm bindings compute the same rules the engine enforces
+    const { G, playerID, bindings } = this.props;
+    return bindings ? bindings.legal_actions(G, playerID) : null;
+  }
+
   getVictoryInfo () {
     let gameover = this.props.ctx.gameover
     if (gameover) {
@@ -51,9 +58,20 @@ class Board extends React.Component {
   }
 
   getPlayerCards() {
-    // Since the state is fully-filtered for this player, we use the first hand in the list.
+    // Opponent hands are filtered out, so only our own seat has cards.
     const { G } = this.props;
-    return G.hands[0];
+    return G.hands[this.props.playerID - 1];
+  }
+
+  getOpponentCards() {
+    // Hands shown at the showdown stay visible while the last result is on display
+    const { G, playerID } = this.props;
+    const opponents = hands => hands.filter((hand, i) => i !== playerID - 1 && hand.length > 0);
+    let shown = opponents(G.hands);
+    if (shown.length === 0) {
+      shown = opponents(G.last_shown_hands || []);
+    }
+    return shown.length > 0 ? [].concat(...shown) : [{}, {}];
   }
 
   getCommunityCards() {
@@ -75,10 +93,10 @@ class Board extends React.Component {
         <Background>
           <CommunityCards cards={this.getCommunityCards()} />
           <PlayerCards cards={this.getPlayerCards()} />
-          <OpponentCards cards={[{}, {}]} />
+          <OpponentCards cards={this.getOpponentCards()} />
         </Background>
         <InformationList G={this.props.G} playerID={this.props.playerID}/>
-        <ActionList lastMove={this.props.G.last_move} onClick={this.onClick}/>
+        <ActionList legal={this.getLegalActions()} onClick={this.onClick}/>
         <GameInfo winner={victoryInfo ? victoryInfo.winner : null} {...this.props} />
       </div>
     );
@@ -7,6 +7,7 @@ const InformationList = function({ G, playerID }) {
   return (
     <div className="InformationList">
       {G.hand_result && <p className="InformationList__item">Hand Result: {G.hand_result}</p>}
+      {G.last_hand_result && <p className="InformationList__item">Last Hand: {G.last_hand_result}</p>}
       <p className="InformationList__item">Last Move: {G.last_move}</p>
       <p className="InformationList__item">Pot: {G.hand_pot}</p>
       <p className="InformationList__item">Chips on Table: {G.chip_table[playerID - 1]}</p>
@@ -58,7 +58,9 @@ class Multiplayer extends React.Component {
       web3c: null,
       game: null,
       proxy: null,
-      token: null
+      token: null,
+      bindings: null,
+      players: [1, 2]
     }
   }
 
@@ -66,6 +68,9 @@ class Multiplayer extends React.Component {
     let params = new URLSearchParams(document.location.search.substring(1)) 
     let gameId = params.get('gameId')
     let rawToken = params.get('token')
+    // The table was created with one seat for every player token
+    let numSeats = parseInt(params.get('seats') || '2', 10)
+    let players = Array.from({ length: numSeats }, (_, i) => i + 1)
 
     if (rawToken) {
       var tokenInfo = codecs('json').decode(Buffer.from(rawToken, 'base64'))
@@ -79,10 +84,13 @@ class Multiplayer extends React.Component {
     let web3c = new Web3c(WS_ENDPOINT)
 
     let game = await this.createGame(gameId, web3c, web3c, newPrivateKey)
-    let proxy = await this.createProxy(game)
+    let bindings = await bindingsPromise
+    let proxy = await this.createProxy(game, bindings, players)
     this.setState({
       game,
       proxy,
+      bindings,
+      players,
       web3c,
       token,
     })
@@ -109,11 +117,10 @@ class Multiplayer extends React.Component {
     return new Game(server, gameId, 14000000)
   }
 
-  async createProxy (game) {
-    let bindings = await bindingsPromise
+  async createProxy (game, bindings, players) {
     let builder = createProxyBuilder(bindings)
     let seed = Math.floor(Math.random() * 100000);
-    return builder([1, 2], game, game.playerId, seed).ready();
+    return builder(players, game, game.playerId, seed).ready();
   }
 
   render () {
@@ -122,11 +129,15 @@ class Multiplayer extends React.Component {
       let game = props.game
 
       let playerId = game.playerId
+      // The board lists the legal actions with the same bindings the proxy runs
+      let bindings = this.state.bindings
+      let BoardWithBindings = props => <Board {...props} bindings={bindings} />
+
       let Player = Client({
-        board: Board,
+        board: BoardWithBindings,
         proxy,
         playerId,
-        players: [1, 2],
+        players: this.state.players,
         multiplayer: game,
         debug: false
       });
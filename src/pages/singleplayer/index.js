@@ -21,21 +21,20 @@ import BrandLogo from '../../assets/OasisLabs_Vertical_Logo_Red_RGB.png';
 
 import '../../assets/index.css';
 
-window.bindingsPromise = bindingsPromise;
-
 const Singleplayer = () => {
   let proxiesPromise = async (resolve, reject) => {
     let bindings = await bindingsPromise;
     let proxyBuilder = createProxyBuilder(bindings);
     let seed = Math.floor(Math.random() * 100000);
     return Promise.all([
+      bindings,
       proxyBuilder([1,2], null, 1, seed).ready(),
       proxyBuilder([1,2], null, 2, seed).ready()
     ]);
   }
 
   return (
-    <Async promise={proxiesPromise()} then={([proxy1, proxy2]) => {
+    <Async promise={proxiesPromise()} then={([bindings, proxy1, proxy2]) => {
       // This simplifies local testing.
       let tee = (function (d1, d2) {
         return (action) => {
@@ -47,8 +46,11 @@ const Singleplayer = () => {
       proxy1.dispatch = tee;
       proxy2.dispatch = tee;
 
+      // The board lists the legal actions with the same bindings the proxies run
+      let BoardWithBindings = props => <Board {...props} bindings={bindings} />;
+
       let PlayerOne = Client({
-        board: Board,
+        board: BoardWithBindings,
         proxy: proxy1,
         playerId: 1,
         players: [1, 2],
@@ -57,7 +59,7 @@ const Singleplayer = () => {
       });
 
       let PlayerTwo = Client({
-        board: Board,
+        board: BoardWithBindings,
         proxy: proxy2,
         playerId: 2,
         players: [1, 2],
@@ -29,6 +29,11 @@ GameServerContract.web3.currentProvider.sendAsync = function () {
 
 console.log('CONFIDENTIAL:', confidential)
 
+// The rules a table is created with, serialized like the TableConfig the contract reads
+function tableConfig (numSeats) {
+  return Buffer.from(JSON.stringify({ num_seats: numSeats }))
+}
+
 async function delay (ms) {
   return new Promise((resolve, reject) => {
     setTimeout(() => {
@@ -61,7 +66,7 @@ contract('GameServerContract', async (accounts) => {
         token: token2,
         is_bot: false
       }
-    ])
+    ], tableConfig(2))
 
     assert.equal(game.id, 1)
 
@@ -70,6 +75,36 @@ contract('GameServerContract', async (accounts) => {
     assert.deepEqual(players, {})
   })
 
+  it('should not create a game with more players than seats', async () => {
+    let server = new GameServer(GameServerContract.address, {
+      privateKey: '0xb5144c6bda090723de712e52b92b4c758d78348ddce9aa80ca8ef51125bfb308',
+      web3c,
+      eventsWeb3c,
+      confidential
+    })
+
+    let token1 = Buffer.allocUnsafe(20)
+    let token2 = Buffer.allocUnsafe(20)
+    sodium.randombytes_buf(token1)
+    sodium.randombytes_buf(token2)
+
+    try {
+      await server.createGame([
+        {
+          token: token1,
+          is_bot: false
+        },
+        {
+          token: token2,
+          is_bot: false
+        }
+      ], tableConfig(3))
+      assert.fail('The game was created')
+    } catch (err) {
+      assert.ok(err)
+    }
+  })
+
   it('should not start a game if both players aren\'t ready', async () => {
     let server = new GameServer(GameServerContract.address, {
       privateKey: '0xb5144c6bda090723de712e52b92b4c758d78348ddce9aa80ca8ef51125bfb308',
@@ -92,7 +127,7 @@ contract('GameServerContract', async (accounts) => {
         token: token2,
         is_bot: false
       }
-    ])
+    ], tableConfig(2))
 
     // Making a move should result in a transaction failure.
     try {
@@ -138,7 +173,7 @@ contract('GameServerContract', async (accounts) => {
         token: token2,
         is_bot: false
       }
-    ])
+    ], tableConfig(2))
     let game2 = new Game(server2, game1.id)
 
     await game1.ready()
@@ -171,6 +206,43 @@ contract('GameServerContract', async (accounts) => {
     })
   })
 
+  it.only('should seat every player of a table with more than two seats', async () => {
+    let servers = [0, 1, 2].map(account => new GameServer(GameServerContract.address, {
+      web3c,
+      eventsWeb3c,
+      account,
+      confidential
+    }))
+    await Promise.all(servers.map(server => server.ready()))
+
+    let tokens = servers.map(() => {
+      let token = Buffer.allocUnsafe(20)
+      sodium.randombytes_buf(token)
+      return token
+    })
+
+    let game1 = await servers[0].createGame(tokens.map(token => {
+      return {
+        token,
+        is_bot: false
+      }
+    }), tableConfig(3))
+    let games = [game1, new Game(servers[1], game1.id), new Game(servers[2], game1.id)]
+
+    for (let i = 0; i < games.length; i++) {
+      await games[i].ready()
+      await games[i].sendReady(tokens[i])
+    }
+
+    // Three handed, player 1 has the button and acts first facing the big blind of player 3.
+    // On a table seated with the default two seats, player 1 would have posted the small blind.
+    let instance = await GameServerContract.deployed()
+    let legal = JSON.parse(Buffer.from((await instance.getLegalActions(game1.id, 1)).slice(2), 'hex'))
+    assert.equal(legal.to_call, 2)
+    let config = JSON.parse(Buffer.from((await instance.getConfig(game1.id)).slice(2), 'hex'))
+    assert.equal(config.num_seats, 3)
+  })
+
   it.skip('should complete a game', async () => {
     let server1 = new GameServer(GameServerContract.address, {
       web3c,
@@ -194,7 +266,7 @@ contract('GameServerContract', async (accounts) => {
         address: accounts[1],
         is_bot: false
       }
-    ])
+    ], tableConfig(2))
     await game1.ready()
 
     let game2 = new Game(server2, game1.id)